@@ -2,7 +2,7 @@
 use super::ros_parser::ros_parser::Selection;
 
 /// Profile for what an ability changes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Ability {
     // Need to implement this later to actually change other things
     pub value: String,
@@ -1,30 +1,63 @@
 use super::ability::{parse_abilities, Ability};
+use super::config::{is_configuration, ArmyConfig, DetachmentConfig};
+use super::cost::{get_total_cost, CostMode};
 use super::unit::Unit;
+use super::weapon::{WeaponProfile, WeaponType};
 
 use super::ros_parser::ros_parser::{Force, Roster};
 
 #[derive(Debug)]
 pub struct Detachment {
     pub name: String,
+    /// Id of the detachment's force in the roster.
+    pub force_id: String,
+    /// Id of the detachment type the force was selected from.
+    pub entry_id: String,
     pub abilities: Vec<Ability>,
     pub units: Vec<Unit>,
+    /// Army rules of the detachment's faction (i.e. "Hammer of the Emperor").
+    pub rules: Vec<String>,
+    /// Choices made for the detachment (i.e. its subfaction and command cost).
+    pub config: DetachmentConfig,
+    /// CP gained and spent by the detachment's selections (i.e. battle size and stratagems).
+    pub cp: f32,
 }
 
 #[derive(Debug)]
 pub struct Army {
     pub detachments: Vec<Detachment>,
     pub cp: f32,
+    pub config: ArmyConfig,
+}
+
+/// Weapons with the same name and profile across the whole army.
+#[derive(Debug, Clone)]
+pub struct WeaponCatalogueEntry {
+    pub name: String,
+    pub profile: WeaponProfile,
+    pub count: u32,
+    /// Names of the units wielding the weapon, once per unit.
+    pub units: Vec<String>,
+    /// Average number of shots from every copy of the weapon. Melee weapons have no shots.
+    /// Grenades count a single throw per unit, as only one model in a unit can throw one.
+    /// Pistols are counted even though a model fires either its pistols or its other weapons.
+    pub total_shots: f32,
 }
 
 impl Detachment {
     pub fn from_force(detachment_force: &Force) -> Result<Self, String> {
         let mut abilities: Vec<Ability> = Vec::new();
         let mut units: Vec<Unit> = Vec::new();
+        let mut config = DetachmentConfig::default();
+        let mut cp: f32 = 0.0;
 
         for selection in &detachment_force.selections.selections {
+            cp += get_total_cost(selection, "CP");
+
             match selection.r#type.as_str() {
+                "upgrade" if is_configuration(selection) => config.add_selection(selection),
                 "upgrade" => abilities.append(&mut parse_abilities(&selection)),
-                "model" | "unit" => units.push(Unit::from_selection(&selection).unwrap()),
+                "model" | "unit" => units.push(Unit::from_selection(selection)?),
                 _ => {
                     return Err("Unknown selection type for force: ".to_string()
                         + selection.r#type.as_str())
@@ -34,10 +67,23 @@ impl Detachment {
 
         Ok(Self {
             name: detachment_force.name.to_owned(),
+            force_id: detachment_force.id.to_owned(),
+            entry_id: detachment_force.entry_id.to_owned(),
             abilities,
             units,
+            rules: match &detachment_force.rules {
+                Some(rules) => rules.categories.iter().map(|r| r.name.to_owned()).collect(),
+                None => Vec::new(),
+            },
+            config,
+            cp,
         })
     }
+
+    /// Points or Power Level of every unit in the detachment.
+    pub fn cost(&self, mode: CostMode) -> f32 {
+        self.units.iter().map(|u| u.cost(mode)).sum()
+    }
 }
 
 impl Army {
@@ -45,7 +91,7 @@ impl Army {
         let mut detachments: Vec<Detachment> = Vec::new();
         
         for force in &roster.forces.forces {
-            detachments.push(Detachment::from_force(force).unwrap());
+            detachments.push(Detachment::from_force(force)?);
         }
 
         let mut cp: f32 = 0.0;
@@ -57,7 +103,102 @@ impl Army {
 
         Ok(Self {
             detachments,
-            cp
+            cp,
+            config: ArmyConfig::from_roster(roster),
         })
     }
+
+    /// Points or Power Level of every detachment in the army.
+    pub fn cost(&self, mode: CostMode) -> f32 {
+        self.detachments.iter().map(|d| d.cost(mode)).sum()
+    }
+
+    /// Checks the army's points or Power Level against the chosen battle size.
+    pub fn check_battle_size(&self, mode: CostMode) -> Option<String> {
+        self.config
+            .battle_size
+            .as_ref()?
+            .check_total(self.cost(mode), mode)
+    }
+
+    /// Groups identical weapons from every unit in every detachment.
+    pub fn weapon_catalogue(&self) -> Vec<WeaponCatalogueEntry> {
+        let mut catalogue: Vec<WeaponCatalogueEntry> = Vec::new();
+
+        for unit in self.detachments.iter().flat_map(|d| d.units.iter()) {
+            // A unit can have the same weapon on more than one model
+            let mut unit_entries: Vec<usize> = Vec::new();
+
+            for weapon in unit.models.iter().flat_map(|m| m.weapons.iter()) {
+                let grenade = weapon.profile.weapon_type == WeaponType::Grenade;
+                let shots = match weapon.profile.attacks {
+                    // Only one grenade is thrown by the unit
+                    Some(attacks) if grenade => attacks.average(),
+                    Some(attacks) => attacks.average() * weapon.number as f32,
+                    None => 0.0,
+                };
+
+                let position = catalogue
+                    .iter()
+                    .position(|e| e.name == weapon.name && e.profile == weapon.profile);
+                let i = match position {
+                    Some(i) => i,
+                    None => {
+                        catalogue.push(WeaponCatalogueEntry {
+                            name: weapon.name.to_owned(),
+                            profile: weapon.profile.clone(),
+                            count: 0,
+                            units: Vec::new(),
+                            total_shots: 0.0,
+                        });
+                        catalogue.len() - 1
+                    }
+                };
+
+                catalogue[i].count += weapon.number as u32;
+                if !grenade || !unit_entries.contains(&i) {
+                    catalogue[i].total_shots += shots;
+                }
+                if !unit_entries.contains(&i) {
+                    catalogue[i].units.push(unit.name.to_owned());
+                    unit_entries.push(i);
+                }
+            }
+        }
+
+        catalogue.sort_by(|a, b| a.name.cmp(&b.name));
+        catalogue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::load_roster;
+
+    #[test]
+    fn catalogue_counts_one_grenade_per_unit() {
+        let army = Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap();
+        let units = army
+            .detachments
+            .iter()
+            .flat_map(|d| d.units.iter())
+            .filter(|u| {
+                u.models
+                    .iter()
+                    .flat_map(|m| m.weapons.iter())
+                    .any(|w| w.name == "Frag grenades")
+            })
+            .count();
+
+        let catalogue = army.weapon_catalogue();
+        let frag_grenades = catalogue
+            .iter()
+            .find(|e| e.name == "Frag grenades")
+            .unwrap();
+
+        assert!(frag_grenades.count as usize > units);
+        assert_eq!(frag_grenades.units.len(), units);
+        assert_eq!(frag_grenades.total_shots, 3.5 * units as f32);
+    }
 }
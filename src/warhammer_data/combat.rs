@@ -0,0 +1,494 @@
+use super::defence::DefensiveTraits;
+use super::edition::Edition;
+use super::unit::ModelProfile;
+use super::weapon::{Weapon, WeaponStrength, WeaponType};
+
+/// Expected results of a weapon attacking a unit of models with the same profile.
+#[derive(Debug, Clone, Copy)]
+pub struct ExpectedDamage {
+    pub attacks: f32,
+    pub hits: f32,
+    pub wounds: f32,
+    pub unsaved_wounds: f32,
+    pub damage: f32,
+    pub models_slain: f32,
+}
+
+/// Chance of rolling the needed value or higher on a D6. Anything above 6 can not be rolled.
+pub fn roll_chance(needed: u8) -> f32 {
+    match needed {
+        0..=1 => 1.0,
+        2..=6 => (7 - needed) as f32 / 6.0,
+        _ => 0.0,
+    }
+}
+
+/// Roll needed to hit with the given skill. An unmodified 1 always fails and a 6 always hits.
+pub fn hit_roll_needed(skill: u8, modifier: i8, edition: Edition) -> u8 {
+    (skill as i8 - edition.limit_hit_modifier(modifier)).clamp(2, 6) as u8
+}
+
+/// Unmodified roll needed to wound from the strength versus toughness table.
+/// The table is the same in 9th and 10th edition.
+pub fn wound_table(strength: u8, toughness: u8) -> u8 {
+    let (strength, toughness) = (strength as u16, toughness as u16);
+
+    if strength >= toughness * 2 {
+        2
+    } else if strength > toughness {
+        3
+    } else if strength == toughness {
+        4
+    } else if strength * 2 > toughness {
+        5
+    } else {
+        6
+    }
+}
+
+/// Roll needed to wound with a weapon used by a model with the given strength after modifiers.
+/// An unmodified 1 always fails and a 6 always wounds.
+pub fn wound_roll_needed(
+    weapon_strength: &WeaponStrength,
+    user_strength: u8,
+    toughness: u8,
+    modifier: i8,
+    edition: Edition,
+) -> u8 {
+    let needed = wound_table(weapon_strength.effective(user_strength), toughness);
+
+    (needed as i8 - edition.limit_wound_modifier(modifier)).clamp(2, 6) as u8
+}
+
+/// Roll needed to save an attack. Uses the invulnerable save if it is better than the armour save
+/// after armour penetration. A save of 0 means the model has no save.
+pub fn save_roll_needed(save: u8, armour_penetration: i8, invulnerable_save: Option<u8>) -> u8 {
+    let armour_save = match save {
+        0 => 7,
+        _ => (save as i8 - armour_penetration).max(2) as u8,
+    };
+
+    match invulnerable_save {
+        Some(invulnerable_save) => armour_save.min(invulnerable_save),
+        None => armour_save,
+    }
+}
+
+/// Probability of each amount of damage from a single unsaved wound after damage modifiers and
+/// feel no pain rolls.
+fn damage_distribution(weapon: &Weapon, defensive_traits: &DefensiveTraits) -> Vec<f32> {
+    let mut damage = vec![0.0; weapon.profile.damage.distribution().len()];
+    for (value, chance) in weapon.profile.damage.distribution().iter().enumerate() {
+        damage[defensive_traits.modify_damage(value as u8) as usize] += chance;
+    }
+
+    let ignore_chance = match defensive_traits.best_feel_no_pain(false) {
+        Some(feel_no_pain) => roll_chance(feel_no_pain),
+        None => 0.0,
+    };
+
+    // Each point of damage is ignored on a successful feel no pain roll
+    let mut distribution = vec![0.0; damage.len()];
+    for (value, chance) in damage.iter().enumerate() {
+        for (lost, lost_chance) in binomial(value, 1.0 - ignore_chance).iter().enumerate() {
+            distribution[lost] += chance * lost_chance;
+        }
+    }
+
+    distribution
+}
+
+/// Probability of each number of successes from a number of rolls with the given chance.
+fn binomial(rolls: usize, chance: f32) -> Vec<f32> {
+    let mut distribution = vec![0.0; rolls + 1];
+    distribution[0] = 1.0;
+
+    for roll in 1..=rolls {
+        for successes in (0..=roll).rev() {
+            let success = match successes {
+                0 => 0.0,
+                _ => distribution[successes - 1] * chance,
+            };
+            distribution[successes] = distribution[successes] * (1.0 - chance) + success;
+        }
+    }
+
+    distribution
+}
+
+/// Calculates the expected damage of a weapon used by the attacker against the defender's profile.
+/// Attacks are made by every copy of the weapon, for both melee and ranged weapons.
+/// Damage from an attack does not carry over to the next model when a model is slain.
+pub fn expected_damage(
+    attacker: &ModelProfile,
+    weapon: &Weapon,
+    defender: &ModelProfile,
+    invulnerable_save: Option<u8>,
+    defensive_traits: &DefensiveTraits,
+    edition: Edition,
+) -> ExpectedDamage {
+    // Melee weapons use the models attacks and skill
+    let (attacks, skill) = match weapon.profile.weapon_type {
+        WeaponType::Melee => (attacker.attacks, attacker.weapon_skill),
+        _ => match weapon.profile.attacks {
+            Some(attacks) => (attacks, attacker.ballistic_skill),
+            None => (attacker.attacks, attacker.ballistic_skill),
+        },
+    };
+    // The weapon's number is how many models are using it
+    let attacks = attacks.multiply(weapon.number);
+
+    // A skill of 0 is a '-' in the profile, so the model can not make these attacks
+    let hit_chance = if weapon.profile.automatically_hits() {
+        1.0
+    } else {
+        match skill {
+            0 => 0.0,
+            _ => roll_chance(hit_roll_needed(skill, 0, edition)),
+        }
+    };
+    let wound_chance = roll_chance(wound_roll_needed(
+        &weapon.profile.strength,
+        attacker.strength,
+        defender.toughness,
+        0,
+        edition,
+    ));
+    let fail_save_chance = 1.0
+        - roll_chance(save_roll_needed(
+            defender.save,
+            weapon.profile.armour_penetration,
+            invulnerable_save,
+        ));
+    let unsaved_chance = hit_chance * wound_chance * fail_save_chance;
+
+    // Probability of each number of unsaved wounds
+    let attack_distribution = attacks.distribution();
+    let mut unsaved_distribution = vec![0.0; attack_distribution.len()];
+    for (number, chance) in attack_distribution.iter().enumerate() {
+        for (unsaved, unsaved_chance) in binomial(number, unsaved_chance).iter().enumerate() {
+            unsaved_distribution[unsaved] += chance * unsaved_chance;
+        }
+    }
+
+    let damage_distribution = damage_distribution(weapon, defensive_traits);
+    let damage_per_wound: f32 = damage_distribution
+        .iter()
+        .enumerate()
+        .map(|(damage, chance)| damage as f32 * chance)
+        .sum();
+
+    // Track the chance of the current model having each number of wounds remaining
+    let wounds = defender.wounds as usize;
+    let mut models_slain = 0.0;
+    if wounds > 0 {
+        let mut remaining = vec![0.0; wounds + 1];
+        remaining[wounds] = 1.0;
+        let mut slain = 0.0;
+
+        for unsaved_chance in unsaved_distribution.iter().skip(1) {
+            let mut next = vec![0.0; wounds + 1];
+            for (current, current_chance) in remaining.iter().enumerate().skip(1) {
+                for (damage, damage_chance) in damage_distribution.iter().enumerate() {
+                    let chance = current_chance * damage_chance;
+                    if damage >= current {
+                        // Model is slain and the next model is at full wounds
+                        slain += chance;
+                        next[wounds] += chance;
+                    } else {
+                        next[current - damage] += chance;
+                    }
+                }
+            }
+            remaining = next;
+            models_slain += unsaved_chance * slain;
+        }
+    }
+
+    let unsaved_wounds = attacks.average() * unsaved_chance;
+
+    ExpectedDamage {
+        attacks: attacks.average(),
+        hits: attacks.average() * hit_chance,
+        wounds: attacks.average() * hit_chance * wound_chance,
+        unsaved_wounds,
+        damage: unsaved_wounds * damage_per_wound,
+        models_slain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::defence::{FeelNoPain, FeelNoPainRestriction};
+    use crate::warhammer_data::profile::ProfileValue;
+    use crate::warhammer_data::weapon::{WeaponProfile, WeaponStrengthType};
+
+    fn profile(skill: u8, strength: u8, toughness: u8, attacks: u8, save: u8) -> ModelProfile {
+        ModelProfile {
+            movement: 6,
+            min_movement: 0,
+            weapon_skill: skill,
+            ballistic_skill: skill,
+            strength,
+            toughness,
+            wounds: 1,
+            attacks: ProfileValue {
+                dice_value: None,
+                flat_value: Some(attacks),
+            },
+            leadership: 7,
+            save,
+            wound_band: None,
+        }
+    }
+
+    fn weapon(weapon_type: WeaponType, attacks: Option<&str>, number: u8) -> Weapon {
+        Weapon {
+            name: "Weapon".to_string(),
+            profile: WeaponProfile {
+                abilities: None,
+                range: Some(24),
+                weapon_type,
+                attacks: attacks.map(ProfileValue::from_str),
+                strength: WeaponStrength {
+                    value: 0,
+                    strength_type: WeaponStrengthType::Addition,
+                },
+                armour_penetration: 0,
+                damage: ProfileValue::from_str("1"),
+            },
+            number,
+        }
+    }
+
+    fn attacks(weapon: &Weapon) -> f32 {
+        expected_damage(
+            &profile(3, 4, 4, 2, 3),
+            weapon,
+            &profile(3, 4, 4, 1, 3),
+            None,
+            &DefensiveTraits::default(),
+            Edition::Ninth,
+        )
+        .attacks
+    }
+
+    #[test]
+    fn wound_table_boundaries() {
+        // (strength, toughness, roll needed)
+        let table = [
+            // Strength is at least double the toughness
+            (8, 4, 2),
+            (10, 4, 2),
+            (2, 1, 2),
+            // Strength is greater than the toughness
+            (7, 4, 3),
+            (5, 4, 3),
+            (3, 2, 3),
+            // Strength is equal to the toughness
+            (4, 4, 4),
+            (1, 1, 4),
+            // Strength is lower than the toughness
+            (3, 4, 5),
+            (3, 5, 5),
+            (5, 9, 5),
+            // Strength is half the toughness or lower
+            (2, 4, 6),
+            (4, 8, 6),
+            (1, 7, 6),
+            (4, 9, 6),
+        ];
+
+        for (strength, toughness, needed) in table {
+            assert_eq!(
+                wound_table(strength, toughness),
+                needed,
+                "S{} against T{}",
+                strength,
+                toughness
+            );
+        }
+    }
+
+    #[test]
+    fn wound_table_does_not_overflow() {
+        assert_eq!(wound_table(u8::MAX, 200), 3);
+        assert_eq!(wound_table(200, u8::MAX), 5);
+    }
+
+    #[test]
+    fn wound_roll_uses_the_weapon_strength() {
+        let strength = |value, strength_type| WeaponStrength {
+            value,
+            strength_type,
+        };
+
+        // User strength 4 against toughness 4
+        let cases = [
+            (strength(0, WeaponStrengthType::Addition), 4),
+            (strength(1, WeaponStrengthType::Addition), 3),
+            (strength(2, WeaponStrengthType::Multiply), 2),
+            (strength(3, WeaponStrengthType::Flat), 5),
+            (strength(2, WeaponStrengthType::Flat), 6),
+        ];
+
+        for edition in [Edition::Ninth, Edition::Tenth] {
+            for (weapon_strength, needed) in &cases {
+                assert_eq!(
+                    wound_roll_needed(weapon_strength, 4, 4, 0, edition),
+                    *needed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn wound_roll_modifiers_are_limited() {
+        let user = WeaponStrength {
+            value: 0,
+            strength_type: WeaponStrengthType::Addition,
+        };
+
+        for edition in [Edition::Ninth, Edition::Tenth] {
+            assert_eq!(wound_roll_needed(&user, 4, 4, 1, edition), 3);
+            assert_eq!(wound_roll_needed(&user, 4, 4, -1, edition), 5);
+            assert_eq!(wound_roll_needed(&user, 4, 4, 3, edition), 3);
+            assert_eq!(wound_roll_needed(&user, 4, 4, -3, edition), 5);
+            // An unmodified 1 always fails and a 6 always wounds
+            assert_eq!(wound_roll_needed(&user, 8, 4, 1, edition), 2);
+            assert_eq!(wound_roll_needed(&user, 2, 4, -1, edition), 6);
+        }
+    }
+
+    #[test]
+    fn hit_roll_modifiers_are_limited() {
+        for edition in [Edition::Ninth, Edition::Tenth] {
+            assert_eq!(hit_roll_needed(3, 0, edition), 3);
+            assert_eq!(hit_roll_needed(3, 1, edition), 2);
+            assert_eq!(hit_roll_needed(3, -1, edition), 4);
+            assert_eq!(hit_roll_needed(3, 2, edition), 2);
+            assert_eq!(hit_roll_needed(3, -2, edition), 4);
+            // An unmodified 1 always fails and a 6 always hits
+            assert_eq!(hit_roll_needed(2, 1, edition), 2);
+            assert_eq!(hit_roll_needed(6, -1, edition), 6);
+        }
+    }
+
+    #[test]
+    fn melee_and_ranged_attacks_scale_with_number() {
+        // Five models with a power sword make five times their attacks
+        assert_eq!(attacks(&weapon(WeaponType::Melee, None, 1)), 2.0);
+        assert_eq!(attacks(&weapon(WeaponType::Melee, None, 5)), 10.0);
+        assert_eq!(attacks(&weapon(WeaponType::RapidFire, Some("1"), 1)), 1.0);
+        assert_eq!(attacks(&weapon(WeaponType::RapidFire, Some("1"), 5)), 5.0);
+    }
+
+    /// Ranged weapon profile with a flat strength.
+    fn ranged(attacks: &str, strength: u8, armour_penetration: i8, damage: &str) -> WeaponProfile {
+        WeaponProfile {
+            abilities: None,
+            range: Some(48),
+            weapon_type: WeaponType::Heavy,
+            attacks: Some(ProfileValue::from_str(attacks)),
+            strength: WeaponStrength {
+                value: strength,
+                strength_type: WeaponStrengthType::Flat,
+            },
+            armour_penetration,
+            damage: ProfileValue::from_str(damage),
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.0001,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn bolters_against_power_armour() {
+        let mut bolter = weapon(WeaponType::RapidFire, Some("1"), 10);
+        bolter.profile = ranged("1", 4, 0, "1");
+
+        let result = expected_damage(
+            &profile(3, 4, 4, 1, 3),
+            &bolter,
+            &profile(3, 4, 4, 1, 3),
+            None,
+            &DefensiveTraits::default(),
+            Edition::Ninth,
+        );
+
+        // 10 shots, hit on 3+, wound on 4+ and saved on 3+
+        assert_close(result.attacks, 10.0);
+        assert_close(result.hits, 10.0 * 4.0 / 6.0);
+        assert_close(result.wounds, 10.0 * 4.0 / 6.0 / 2.0);
+        assert_close(result.unsaved_wounds, 10.0 / 9.0);
+        assert_close(result.damage, 10.0 / 9.0);
+        assert_close(result.models_slain, 10.0 / 9.0);
+    }
+
+    #[test]
+    fn dice_damage_with_feel_no_pain() {
+        let mut lascannon = weapon(WeaponType::Heavy, Some("1"), 1);
+        lascannon.profile = ranged("1", 9, -3, "D6");
+        let mut defender = profile(3, 4, 7, 1, 3);
+        defender.wounds = 3;
+        let traits = DefensiveTraits {
+            feel_no_pain: vec![FeelNoPain {
+                value: 5,
+                restriction: FeelNoPainRestriction::All,
+            }],
+            ..Default::default()
+        };
+
+        let result = expected_damage(
+            &profile(3, 4, 4, 1, 3),
+            &lascannon,
+            &defender,
+            None,
+            &traits,
+            Edition::Ninth,
+        );
+
+        // Hit on 3+, wound on 3+ and saved on 6+
+        let unsaved = 2.0 / 3.0 * 2.0 / 3.0 * 5.0 / 6.0;
+        assert_close(result.unsaved_wounds, unsaved);
+        // Each point of damage is lost on a 1-4
+        assert_close(result.damage, unsaved * 3.5 * 2.0 / 3.0);
+        // Chance of losing at least 3 wounds from a D6 of 3, 4, 5 and 6
+        let slain = (216.0 + 432.0 + 576.0 + 656.0) / 729.0 / 6.0;
+        assert_close(result.models_slain, unsaved * slain);
+    }
+
+    #[test]
+    fn excess_damage_is_lost_when_a_model_is_slain() {
+        let mut missile = weapon(WeaponType::Heavy, Some("2"), 1);
+        missile.profile = ranged("2", 8, -4, "D3");
+        let mut defender = profile(3, 4, 4, 1, 3);
+        defender.wounds = 2;
+
+        let result = expected_damage(
+            &profile(3, 4, 4, 1, 3),
+            &missile,
+            &defender,
+            None,
+            &DefensiveTraits::default(),
+            Edition::Ninth,
+        );
+
+        // Hit on 3+, wound on 2+ and no save
+        assert_close(result.unsaved_wounds, 2.0 * 5.0 / 9.0);
+        assert_close(result.damage, 2.0 * 5.0 / 9.0 * 2.0);
+        // One unsaved wound slays on a 2+, two slay one model and a second on a 2+ if the
+        // first was slain
+        let one = 40.0 / 81.0 * 2.0 / 3.0;
+        let two = 25.0 / 81.0 * (2.0 / 3.0 * 5.0 / 3.0 + 1.0 / 3.0);
+        assert_close(result.models_slain, one + two);
+        assert!(result.models_slain < result.damage / 2.0);
+    }
+}
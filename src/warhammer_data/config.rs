@@ -0,0 +1,367 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::ability::{parse_abilities, Ability};
+use super::cost::{get_total_cost, CostMode};
+use super::ros_parser::ros_parser::{Roster, Selection};
+
+/// Configuration selections that are settings for the whole army.
+const ARMY_SELECTIONS: [&str; 2] = ["Battle Size", "Gametype"];
+
+/// Selections that choose the detachment's subfaction (i.e. "Regimental Doctrine").
+const SUBFACTION_SELECTIONS: [&str; 6] = [
+    "Regimental Doctrine",
+    "Questor Allegiance",
+    "Household Choice",
+    "Craftworld Selection",
+    "Chapter Selection",
+    "Forge World Choice",
+];
+
+/// Prefixes of subfaction names that are not part of the name.
+const SUBFACTION_PREFIXES: [&str; 4] = ["Regiment: ", "Chapter: ", "Household: ", "Forge World: "];
+
+/// Battle sizes from the 9th edition core rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BattleSizeType {
+    CombatPatrol,
+    Incursion,
+    StrikeForce,
+    Onslaught,
+}
+
+/// Battle size and its points and Power Level bands
+/// (i.e. "2. Incursion (51-100 Total PL / 501-1000 Points)").
+#[derive(Debug, Clone, PartialEq)]
+pub struct BattleSize {
+    pub size: BattleSizeType,
+    pub name: String,
+    pub min_power_level: f32,
+    pub max_power_level: f32,
+    pub min_points: f32,
+    pub max_points: f32,
+}
+
+/// Type of game the roster is made for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameType {
+    Open,
+    Narrative,
+    Matched,
+}
+
+/// Army settings from the roster's configuration selections.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArmyConfig {
+    pub battle_size: Option<BattleSize>,
+    pub game_type: Option<GameType>,
+    /// Settings that could not be read (i.e. an unknown battle size), which are left as `None`.
+    pub problems: Vec<String>,
+}
+
+/// Other option chosen for a detachment (i.e. "Chivalric Oath" with "Mixed Army").
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOption {
+    pub name: String,
+    /// Names of the chosen selections, empty if the option is a toggle (i.e. "Use Beta Rules").
+    pub choices: Vec<String>,
+}
+
+/// Detachment settings from the force's configuration selections.
+#[derive(Debug, Clone, Default)]
+pub struct DetachmentConfig {
+    /// Chosen subfaction without its prefix (i.e. "Cadian" from "Regiment: Cadian").
+    pub subfaction: Option<String>,
+    /// Abilities from the chosen subfaction (i.e. "Born Soldiers").
+    pub doctrines: Vec<Ability>,
+    /// CP gained or spent for the detachment, CP spent is negative.
+    pub command_cost: f32,
+    pub options: Vec<ConfigOption>,
+}
+
+/// Checks if the selection is a configuration selection and not a rule.
+pub fn is_configuration(selection: &Selection) -> bool {
+    let name = selection.name.trim();
+    let configuration_category = match &selection.categories {
+        Some(categories) => categories
+            .categories
+            .iter()
+            .any(|c| c.primary && c.name == "Configuration"),
+        None => false,
+    };
+
+    configuration_category
+        || ARMY_SELECTIONS.contains(&name)
+        || SUBFACTION_SELECTIONS.contains(&name)
+        || name == "Detachment Command Cost"
+}
+
+impl BattleSize {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        lazy_static! {
+            // Regex to get the bands (i.e. "(51-100 Total PL / 501-1000 Points)")
+            static ref BAND_RE: Regex =
+                Regex::new(r"\((\d+)-(\d+) Total PL / (\d+)-(\d+) Points\)").unwrap();
+        }
+
+        let size = if name.contains("Combat Patrol") {
+            BattleSizeType::CombatPatrol
+        } else if name.contains("Incursion") {
+            BattleSizeType::Incursion
+        } else if name.contains("Strike Force") {
+            BattleSizeType::StrikeForce
+        } else if name.contains("Onslaught") {
+            BattleSizeType::Onslaught
+        } else {
+            return Err("Unknown battle size: ".to_string() + name);
+        };
+
+        let captures = BAND_RE
+            .captures(name)
+            .ok_or("Battle size does not have points: ".to_string() + name)?;
+
+        Ok(Self {
+            size,
+            name: name.trim().to_owned(),
+            min_power_level: captures[1].parse().unwrap(),
+            max_power_level: captures[2].parse().unwrap(),
+            min_points: captures[3].parse().unwrap(),
+            max_points: captures[4].parse().unwrap(),
+        })
+    }
+
+    /// Command Points each player starts with.
+    pub fn starting_cp(&self) -> u8 {
+        match self.size {
+            BattleSizeType::CombatPatrol => 3,
+            BattleSizeType::Incursion => 6,
+            BattleSizeType::StrikeForce => 12,
+            BattleSizeType::Onslaught => 18,
+        }
+    }
+
+    /// Most points or Power Level an army can have.
+    pub fn limit(&self, mode: CostMode) -> f32 {
+        match mode {
+            CostMode::Points => self.max_points,
+            CostMode::PowerLevel => self.max_power_level,
+        }
+    }
+
+    /// Checks an army's total against the battle size, returning a problem if it is over.
+    pub fn check_total(&self, total: f32, mode: CostMode) -> Option<String> {
+        if total > self.limit(mode) {
+            Some(format!(
+                "{}{} is over the {}{} limit of {}",
+                total,
+                mode.unit_name(),
+                self.limit(mode),
+                mode.unit_name(),
+                self.name
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl GameType {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.trim() {
+            "Open" | "Open Play" => Ok(GameType::Open),
+            "Narrative" | "Narrative Play" => Ok(GameType::Narrative),
+            "Matched" | "Matched Play" => Ok(GameType::Matched),
+            _ => Err("Unknown game type: ".to_string() + name),
+        }
+    }
+}
+
+/// Gets the name of the option chosen in a configuration selection.
+fn chosen_option(selection: &Selection) -> Option<&str> {
+    selection
+        .selections
+        .iter()
+        .flat_map(|s| s.selections.first())
+        .map(|s| s.name.as_str())
+        .next()
+}
+
+impl DetachmentConfig {
+    /// Adds a configuration selection to the detachment's settings. Army settings are skipped
+    /// as they are part of the army's config.
+    pub fn add_selection(&mut self, selection: &Selection) {
+        let name = selection.name.trim();
+
+        if ARMY_SELECTIONS.contains(&name) {
+            return;
+        }
+
+        if name == "Detachment Command Cost" {
+            self.command_cost += get_total_cost(selection, "CP");
+        } else if SUBFACTION_SELECTIONS.contains(&name) {
+            self.subfaction = chosen_option(selection).map(|option| {
+                let option = option.trim();
+                SUBFACTION_PREFIXES
+                    .iter()
+                    .find_map(|prefix| option.strip_prefix(prefix))
+                    .unwrap_or(option)
+                    .to_owned()
+            });
+            self.doctrines.append(&mut parse_abilities(selection));
+        } else {
+            self.options.push(ConfigOption {
+                name: name.to_owned(),
+                choices: selection
+                    .selections
+                    .iter()
+                    .flat_map(|s| s.selections.iter())
+                    .map(|s| s.name.trim().to_owned())
+                    .collect(),
+            });
+        }
+    }
+}
+
+impl ArmyConfig {
+    /// Gets the settings from every detachment. The first detachment with a setting is used.
+    pub fn from_roster(roster: &Roster) -> Self {
+        let mut config = Self::default();
+
+        for force in &roster.forces.forces {
+            for selection in &force.selections.selections {
+                let option = match chosen_option(selection) {
+                    Some(option) => option,
+                    None => continue,
+                };
+
+                match selection.name.trim() {
+                    "Battle Size" if config.battle_size.is_none() => {
+                        match BattleSize::from_name(option) {
+                            Ok(battle_size) => config.battle_size = Some(battle_size),
+                            Err(problem) => config.problems.push(problem),
+                        }
+                    }
+                    "Gametype" if config.game_type.is_none() => match GameType::from_name(option) {
+                        Ok(game_type) => config.game_type = Some(game_type),
+                        Err(problem) => config.problems.push(problem),
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::army::Army;
+    use crate::warhammer_data::load_roster;
+
+    /// Renames the option chosen in the first of the roster's selections with the name.
+    fn choose(roster: &mut Roster, name: &str, option: &str) {
+        let selection = roster
+            .forces
+            .forces
+            .iter_mut()
+            .flat_map(|f| f.selections.selections.iter_mut())
+            .find(|s| s.name.trim() == name)
+            .unwrap();
+
+        selection.selections.as_mut().unwrap().selections[0].name = option.to_owned();
+    }
+
+    #[test]
+    fn reads_battle_size_and_game_type() {
+        let config = ArmyConfig::from_roster(&load_roster("Astra_1000/Astra_1000.ros"));
+
+        assert_eq!(config.battle_size.unwrap().size, BattleSizeType::Incursion);
+        assert_eq!(config.game_type, Some(GameType::Open));
+        assert!(config.problems.is_empty());
+    }
+
+    #[test]
+    fn unknown_settings_are_problems() {
+        let mut roster = load_roster("Astra_1000/Astra_1000.ros");
+        choose(&mut roster, "Battle Size", "5. Apocalypse");
+        choose(&mut roster, "Gametype", "Crusade");
+
+        let config = ArmyConfig::from_roster(&roster);
+
+        assert_eq!(config.battle_size, None);
+        assert_eq!(config.game_type, None);
+        assert_eq!(
+            config.problems,
+            [
+                "Unknown game type: Crusade",
+                "Unknown battle size: 5. Apocalypse"
+            ]
+        );
+    }
+
+    #[test]
+    fn selection_names_are_trimmed() {
+        let mut roster = load_roster("Astra_1000/Astra_1000.ros");
+        for selection in roster.forces.forces[0].selections.selections.iter_mut() {
+            selection.name = format!(" {} ", selection.name);
+        }
+
+        let config = ArmyConfig::from_roster(&roster);
+
+        assert!(config.battle_size.is_some());
+        assert!(config.game_type.is_some());
+    }
+
+    #[test]
+    fn configuration_selections() {
+        let roster = load_roster("Astra_1000/Astra_1000.ros");
+        let configuration: Vec<&str> = roster
+            .forces
+            .forces
+            .iter()
+            .flat_map(|f| f.selections.selections.iter())
+            .filter(|s| is_configuration(s))
+            .map(|s| s.name.as_str())
+            .collect();
+
+        // Chivalric Oath is only found by its category, Whiteshields are a stratagem
+        assert_eq!(
+            configuration,
+            [
+                "Regimental Doctrine",
+                "Gametype",
+                "Detachment Command Cost",
+                "Battle Size",
+                "Questor Allegiance",
+                "Detachment Command Cost",
+                "Chivalric Oath",
+            ]
+        );
+    }
+
+    #[test]
+    fn detachment_settings() {
+        let army = Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap();
+
+        let battalion = &army.detachments[0].config;
+        assert_eq!(battalion.subfaction.as_deref(), Some("Cadian"));
+        assert_eq!(battalion.command_cost, 0.0);
+        let doctrines: Vec<&str> = battalion
+            .doctrines
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(doctrines, ["Born Soldiers"]);
+        // Battle size and game type are part of the army's config
+        assert!(battalion.options.is_empty());
+
+        let knights = &army.detachments[1].config;
+        assert_eq!(knights.subfaction.as_deref(), Some("Questor Imperialis"));
+        assert_eq!(knights.command_cost, -3.0);
+        assert_eq!(knights.options.len(), 1);
+        assert_eq!(knights.options[0].name, "Chivalric Oath");
+        assert_eq!(knights.options[0].choices, ["Mixed Army"]);
+    }
+}
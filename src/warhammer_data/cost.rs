@@ -0,0 +1,93 @@
+use super::ros_parser::ros_parser::{Costs, Selection};
+
+/// Cost used to size an army, points for matched play or Power Level for narrative games.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CostMode {
+    Points,
+    PowerLevel,
+}
+
+impl CostMode {
+    /// Name of the cost in the roster. Power Level has a leading space.
+    pub fn cost_name(&self) -> &'static str {
+        match self {
+            CostMode::Points => "pts",
+            CostMode::PowerLevel => " PL",
+        }
+    }
+
+    pub fn unit_name(&self) -> &'static str {
+        match self {
+            CostMode::Points => "pts",
+            CostMode::PowerLevel => "PL",
+        }
+    }
+}
+
+/// Sums a cost type from a list of costs.
+pub fn sum_costs(costs: &Costs, name: &str) -> f32 {
+    costs
+        .costs
+        .iter()
+        .filter(|cost| cost.name == name)
+        .map(|cost| cost.value)
+        .sum()
+}
+
+/// Gets the value of a cost type from a list of costs that may be missing.
+pub fn get_cost(costs: &Option<Costs>, name: &str) -> f32 {
+    match costs {
+        Some(costs) => sum_costs(costs, name),
+        None => 0.0,
+    }
+}
+
+/// Sums a cost type of the selection and all of its selections.
+pub fn get_total_cost(selection: &Selection, name: &str) -> f32 {
+    let total: f32 = selection
+        .selections
+        .iter()
+        .flat_map(|s| s.selections.iter())
+        .map(|selection| get_total_cost(selection, name))
+        .sum();
+
+    total + get_cost(&selection.costs, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::load_roster;
+
+    #[test]
+    fn cost_names_match_the_roster() {
+        assert_eq!(CostMode::Points.cost_name(), "pts");
+        assert_eq!(CostMode::PowerLevel.cost_name(), " PL");
+        assert_eq!(CostMode::Points.unit_name(), "pts");
+        assert_eq!(CostMode::PowerLevel.unit_name(), "PL");
+    }
+
+    #[test]
+    fn total_cost_includes_nested_selections() {
+        let roster = load_roster("Astra_1000/Astra_1000.ros");
+        let leman_russ = roster
+            .forces
+            .forces
+            .iter()
+            .flat_map(|f| f.selections.selections.iter())
+            .find(|s| s.name == "Leman Russ Battle Tanks")
+            .unwrap();
+
+        // The unit selection itself is free, the points are on the tank and its weapons
+        assert_eq!(
+            get_cost(&leman_russ.costs, CostMode::Points.cost_name()),
+            0.0
+        );
+        assert_eq!(
+            get_total_cost(leman_russ, CostMode::Points.cost_name()),
+            210.0
+        );
+        assert_eq!(get_total_cost(leman_russ, "CP"), 0.0);
+        assert_eq!(get_cost(&None, "pts"), 0.0);
+    }
+}
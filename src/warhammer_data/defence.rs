@@ -0,0 +1,411 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::ability::Ability;
+
+/// Attacks that a save can be used against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SaveRestriction {
+    All,
+    Ranged,
+    Melee,
+}
+
+/// Invulnerable save of a model, which can be limited to ranged or melee attacks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvulnerableSave {
+    pub value: u8,
+    pub restriction: SaveRestriction,
+}
+
+impl InvulnerableSave {
+    /// Parses an invulnerable save from ability text (i.e. "This model has a 5+ invulnerable save").
+    /// 10th edition datasheets have an "Invulnerable Save" ability with only the value (i.e. "4+").
+    pub fn from_ability(ability: &Ability) -> Option<Self> {
+        lazy_static! {
+            // Regex to get the value and the rest of the sentence which can limit the save
+            static ref INVUL_RE: Regex =
+                Regex::new(r"(?i)(\d)\+ invul(?:nerable)? sav(?:e|ing throw)([^.]*)").unwrap();
+            // Regex to get the value from the 10th edition ability
+            static ref VALUE_RE: Regex = Regex::new(r"^(\d)\+\+?$").unwrap();
+        }
+
+        if ability.name.eq_ignore_ascii_case("Invulnerable Save") {
+            if let Some(captures) = VALUE_RE.captures(ability.value.trim()) {
+                return Some(Self {
+                    value: captures[1].parse().unwrap(),
+                    restriction: SaveRestriction::All,
+                });
+            }
+        }
+
+        let captures = INVUL_RE.captures(&ability.value)?;
+        let condition = captures[2].to_lowercase();
+
+        Some(Self {
+            value: captures[1].parse().unwrap(),
+            restriction: if condition.contains("ranged") || condition.contains("shooting") {
+                SaveRestriction::Ranged
+            } else if condition.contains("melee") || condition.contains("fight") {
+                SaveRestriction::Melee
+            } else {
+                SaveRestriction::All
+            },
+        })
+    }
+
+    /// Parses every invulnerable save from the abilities.
+    pub fn from_abilities(abilities: &[Ability]) -> Vec<Self> {
+        let mut saves: Vec<Self> = abilities.iter().filter_map(Self::from_ability).collect();
+        saves.dedup();
+        saves
+    }
+
+    /// Checks if the save can be used against a ranged or melee attack.
+    pub fn applies_to(&self, ranged: bool) -> bool {
+        match self.restriction {
+            SaveRestriction::All => true,
+            SaveRestriction::Ranged => ranged,
+            SaveRestriction::Melee => !ranged,
+        }
+    }
+
+    /// Gets the best save that can be used against a ranged or melee attack.
+    pub fn best(saves: &[Self], ranged: bool) -> Option<u8> {
+        saves
+            .iter()
+            .filter(|save| save.applies_to(ranged))
+            .map(|save| save.value)
+            .min()
+    }
+}
+
+/// Damage that a feel no pain can be used against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeelNoPainRestriction {
+    All,
+    MortalWounds,
+    ExceptMortalWounds,
+}
+
+/// Roll to ignore each wound that would be lost (i.e. "on a 5+, that wound is not lost").
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeelNoPain {
+    pub value: u8,
+    pub restriction: FeelNoPainRestriction,
+}
+
+/// Defensive abilities that change the damage a model takes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DefensiveTraits {
+    pub feel_no_pain: Vec<FeelNoPain>,
+    /// Subtracted from the damage of each attack (to a minimum of 1).
+    pub damage_reduction: u8,
+    /// Damage of each attack is halved (rounding up).
+    pub halve_damage: bool,
+}
+
+impl FeelNoPain {
+    /// Parses a feel no pain from ability text
+    /// (i.e. "Each time a model in this unit would lose a wound, roll one D6: on a 5+, that wound is not lost").
+    /// Older abilities roll first (i.e. "Roll a D6 each time a model in this unit loses a wound. On a 6, ...").
+    /// 10th edition datasheets have a "Feel No Pain" ability with only the value (i.e. "5+").
+    pub fn from_ability(ability: &Ability) -> Option<Self> {
+        lazy_static! {
+            // Regex to get the condition for losing a wound and the value
+            static ref FNP_RE: Regex = Regex::new(
+                r"(?i)(?:would lose|loses) a wound([^:;.]*?)[:;,.]? roll (?:one|a) D6[^.]*?on a (?:roll of )?(\d)\+?,? (?:that wound is not lost|the wound is ignored|it is not lost|the model does not lose that wound|the damage is ignored)"
+            )
+            .unwrap();
+            // Regex to get the condition and value when the roll comes first
+            static ref ROLL_FIRST_RE: Regex = Regex::new(
+                r"(?i)roll (?:one|a) D6 each time a model[^.]*?(?:would lose|loses) a wound([^.]*)\.\s*on a (?:roll of )?(\d)\+?,? (?:that wound is not lost|the wound is ignored|it is not lost|the model does not lose that wound|the damage is ignored)"
+            )
+            .unwrap();
+            // Regex to get the value from the 10th edition ability
+            static ref VALUE_RE: Regex = Regex::new(r"(?i)feel no pain (\d)\+").unwrap();
+        }
+
+        let captures = FNP_RE
+            .captures(&ability.value)
+            .or_else(|| ROLL_FIRST_RE.captures(&ability.value));
+        if let Some(captures) = captures {
+            let condition = captures[1].to_lowercase();
+
+            return Some(Self {
+                value: captures[2].parse().unwrap(),
+                restriction: if condition.contains("mortal wound") {
+                    if condition.contains("except")
+                        || condition.contains("excluding")
+                        || condition.contains("other than")
+                    {
+                        FeelNoPainRestriction::ExceptMortalWounds
+                    } else {
+                        FeelNoPainRestriction::MortalWounds
+                    }
+                } else {
+                    FeelNoPainRestriction::All
+                },
+            });
+        }
+
+        // The name is included for abilities that are only the value
+        let text = ability.name.to_owned() + " " + ability.value.trim();
+        let captures = VALUE_RE.captures(&text)?;
+        let condition = ability.value.to_lowercase();
+
+        Some(Self {
+            value: captures[1].parse().unwrap(),
+            restriction: if condition.contains("mortal wound") {
+                FeelNoPainRestriction::MortalWounds
+            } else {
+                FeelNoPainRestriction::All
+            },
+        })
+    }
+
+    /// Checks if the feel no pain can be used against a mortal wound or normal damage.
+    pub fn applies_to(&self, mortal_wound: bool) -> bool {
+        match self.restriction {
+            FeelNoPainRestriction::All => true,
+            FeelNoPainRestriction::MortalWounds => mortal_wound,
+            FeelNoPainRestriction::ExceptMortalWounds => !mortal_wound,
+        }
+    }
+}
+
+impl DefensiveTraits {
+    /// Parses feel no pains and damage changes from the abilities.
+    pub fn from_abilities(abilities: &[Ability]) -> Self {
+        lazy_static! {
+            // Regex to get flat damage reduction (i.e. "subtract 1 from the Damage characteristic")
+            static ref REDUCTION_RE: Regex =
+                Regex::new(r"(?i)subtract (\d) from the damage characteristic").unwrap();
+            // Regex to check for damage halving (i.e. "halve the Damage characteristic")
+            static ref HALVE_RE: Regex = Regex::new(r"(?i)halve the damage characteristic").unwrap();
+        }
+
+        let mut traits = Self::default();
+
+        for ability in abilities {
+            if let Some(feel_no_pain) = FeelNoPain::from_ability(ability) {
+                if !traits.feel_no_pain.contains(&feel_no_pain) {
+                    traits.feel_no_pain.push(feel_no_pain);
+                }
+            }
+
+            // Damage reductions do not stack, so use the biggest one
+            if let Some(captures) = REDUCTION_RE.captures(&ability.value) {
+                traits.damage_reduction = traits.damage_reduction.max(captures[1].parse().unwrap());
+            }
+
+            if HALVE_RE.is_match(&ability.value) {
+                traits.halve_damage = true;
+            }
+        }
+
+        traits
+    }
+
+    /// Checks if there are any defensive traits.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Gets the best feel no pain that can be used against a mortal wound or normal damage.
+    pub fn best_feel_no_pain(&self, mortal_wound: bool) -> Option<u8> {
+        self.feel_no_pain
+            .iter()
+            .filter(|feel_no_pain| feel_no_pain.applies_to(mortal_wound))
+            .map(|feel_no_pain| feel_no_pain.value)
+            .min()
+    }
+
+    /// Changes the damage of an attack. Halving is done before subtracting and the damage can not
+    /// be reduced below 1.
+    pub fn modify_damage(&self, damage: u8) -> u8 {
+        if damage == 0 {
+            return 0;
+        }
+
+        let damage = if self.halve_damage {
+            damage.div_ceil(2)
+        } else {
+            damage
+        };
+
+        damage.saturating_sub(self.damage_reduction).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ability(name: &str, value: &str) -> Ability {
+        Ability {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn feel_no_pain(value: &str) -> Option<FeelNoPain> {
+        FeelNoPain::from_ability(&ability("Ability", value))
+    }
+
+    #[test]
+    fn feel_no_pain_from_disgustingly_resilient() {
+        let expected = Some(FeelNoPain {
+            value: 5,
+            restriction: FeelNoPainRestriction::All,
+        });
+
+        // 9th edition wording
+        assert_eq!(
+            feel_no_pain("Each time a model in this unit would lose a wound, roll one D6: on a 5+, that wound is not lost."),
+            expected
+        );
+        // 8th edition wording
+        assert_eq!(
+            feel_no_pain("Each time a model in this unit loses a wound, roll a D6; on a roll of 5+ the model does not lose that wound."),
+            expected
+        );
+    }
+
+    #[test]
+    fn feel_no_pain_from_medi_pack() {
+        assert_eq!(
+            feel_no_pain("Roll a D6 each time a model in the bearer's unit loses a wound. On a 6, the model does not lose that wound."),
+            Some(FeelNoPain {
+                value: 6,
+                restriction: FeelNoPainRestriction::All,
+            })
+        );
+    }
+
+    #[test]
+    fn feel_no_pain_against_mortal_wounds() {
+        let feel_no_pain = feel_no_pain(
+            "Each time a model in this unit would lose a wound as the result of a mortal wound, roll one D6: on a 5+, that wound is not lost.",
+        )
+        .unwrap();
+
+        assert_eq!(
+            feel_no_pain.restriction,
+            FeelNoPainRestriction::MortalWounds
+        );
+        assert!(feel_no_pain.applies_to(true));
+        assert!(!feel_no_pain.applies_to(false));
+    }
+
+    #[test]
+    fn feel_no_pain_except_mortal_wounds() {
+        let feel_no_pain = feel_no_pain(
+            "Each time a model in this unit would lose a wound, other than as the result of a mortal wound, roll one D6: on a 6, that wound is not lost.",
+        )
+        .unwrap();
+
+        assert_eq!(feel_no_pain.value, 6);
+        assert_eq!(
+            feel_no_pain.restriction,
+            FeelNoPainRestriction::ExceptMortalWounds
+        );
+        assert!(!feel_no_pain.applies_to(true));
+        assert!(feel_no_pain.applies_to(false));
+    }
+
+    #[test]
+    fn feel_no_pain_from_10th_edition_value() {
+        assert_eq!(
+            FeelNoPain::from_ability(&ability("Feel No Pain", "5+")),
+            Some(FeelNoPain {
+                value: 5,
+                restriction: FeelNoPainRestriction::All,
+            })
+        );
+    }
+
+    #[test]
+    fn other_abilities_are_not_feel_no_pain() {
+        assert_eq!(
+            feel_no_pain("Each time an attack is allocated to a model in this unit, subtract 1 from the Damage characteristic of that attack (to a minimum of 1)."),
+            None
+        );
+        assert_eq!(
+            feel_no_pain(
+                "Each time a model in this unit would lose a wound, it is destroyed on a 6."
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn best_feel_no_pain_is_used() {
+        let traits = DefensiveTraits::from_abilities(&[
+            ability("Disgustingly Resilient", "Each time a model in this unit would lose a wound, roll one D6: on a 5+, that wound is not lost."),
+            ability("Medi-pack", "Roll a D6 each time a model in the bearer's unit loses a wound. On a 6, the model does not lose that wound."),
+            ability("Aegis", "Each time a model in this unit would lose a wound as the result of a mortal wound, roll one D6: on a 4+, that wound is not lost."),
+        ]);
+
+        assert_eq!(traits.best_feel_no_pain(false), Some(5));
+        assert_eq!(traits.best_feel_no_pain(true), Some(4));
+    }
+
+    #[test]
+    fn damage_reduction_from_roster_abilities() {
+        let traits = DefensiveTraits::from_abilities(&[ability(
+            "Wraithbone Form",
+            "Each time an attack is allocated to a model in this unit, subtract 1 from the Damage characteristic of that attack (to a minimum of 1)",
+        )]);
+
+        assert_eq!(traits.damage_reduction, 1);
+        assert!(!traits.halve_damage);
+        assert!(traits.feel_no_pain.is_empty());
+    }
+
+    #[test]
+    fn halve_damage_from_roster_abilities() {
+        let traits = DefensiveTraits::from_abilities(&[ability(
+            "Deathly Form",
+            "Each time an attack is allocated to this model, halve the Damage characteristic of that attack (rounding up)",
+        )]);
+
+        assert!(traits.halve_damage);
+        assert_eq!(traits.damage_reduction, 0);
+    }
+
+    #[test]
+    fn modify_damage_edge_cases() {
+        let reduce = DefensiveTraits {
+            damage_reduction: 1,
+            ..Default::default()
+        };
+        let halve = DefensiveTraits {
+            halve_damage: true,
+            ..Default::default()
+        };
+        let both = DefensiveTraits {
+            damage_reduction: 1,
+            halve_damage: true,
+            ..Default::default()
+        };
+
+        // No damage stays at 0
+        assert_eq!(reduce.modify_damage(0), 0);
+        assert_eq!(halve.modify_damage(0), 0);
+        assert_eq!(both.modify_damage(0), 0);
+        // Damage can not be reduced below 1
+        assert_eq!(reduce.modify_damage(1), 1);
+        assert_eq!(halve.modify_damage(1), 1);
+        assert_eq!(both.modify_damage(1), 1);
+        assert_eq!(reduce.modify_damage(3), 2);
+        // Halving rounds up
+        assert_eq!(halve.modify_damage(3), 2);
+        assert_eq!(halve.modify_damage(6), 3);
+        // Halving is done before subtracting
+        assert_eq!(both.modify_damage(3), 1);
+        assert_eq!(both.modify_damage(6), 2);
+        assert_eq!(both.modify_damage(7), 3);
+        assert_eq!(DefensiveTraits::default().modify_damage(4), 4);
+    }
+}
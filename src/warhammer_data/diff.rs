@@ -0,0 +1,379 @@
+use super::army::{Army, Detachment};
+use super::unit::Unit;
+use super::validation::datasheet_id;
+
+/// How something changed between two versions of an army.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeType {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Change in the number of a weapon or wargear (i.e. "Lasgun" from 9 to 8).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemChange {
+    pub name: String,
+    pub before: u32,
+    pub after: u32,
+}
+
+/// Changes to a unit. Added and removed units compare against a unit with nothing.
+#[derive(Debug, Clone)]
+pub struct UnitDiff {
+    pub name: String,
+    pub change: ChangeType,
+    pub models_before: u32,
+    pub models_after: u32,
+    pub weapons: Vec<ItemChange>,
+    pub wargear: Vec<ItemChange>,
+    /// Points gained, points lost are negative.
+    pub points: f32,
+    pub power_level: f32,
+}
+
+/// Changes to a detachment and its units.
+#[derive(Debug, Clone)]
+pub struct DetachmentDiff {
+    pub name: String,
+    pub change: ChangeType,
+    pub units: Vec<UnitDiff>,
+    pub points: f32,
+    pub power_level: f32,
+    pub cp: f32,
+}
+
+/// Changes from one version of an army to another.
+#[derive(Debug, Clone)]
+pub struct ArmyDiff {
+    pub detachments: Vec<DetachmentDiff>,
+    pub points: f32,
+    pub power_level: f32,
+    pub cp: f32,
+}
+
+/// Counts items with the same name.
+fn count_items<'a>(items: impl Iterator<Item = (&'a str, u8)>) -> Vec<(String, u32)> {
+    let mut counts: Vec<(String, u32)> = Vec::new();
+
+    for (name, number) in items {
+        match counts.iter_mut().find(|(n, _)| n == name) {
+            Some((_, count)) => *count += number as u32,
+            None => counts.push((name.to_owned(), number as u32)),
+        }
+    }
+
+    counts
+}
+
+/// Compares the counts of items, only items with a different count are returned.
+fn item_changes(before: &[(String, u32)], after: &[(String, u32)]) -> Vec<ItemChange> {
+    let count = |counts: &[(String, u32)], name: &str| {
+        counts
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, count)| *count)
+            .unwrap_or(0)
+    };
+
+    let mut changes: Vec<ItemChange> = Vec::new();
+    for (name, _) in before.iter().chain(after.iter()) {
+        if changes.iter().any(|c| &c.name == name) {
+            continue;
+        }
+
+        let change = ItemChange {
+            name: name.to_owned(),
+            before: count(before, name),
+            after: count(after, name),
+        };
+        if change.before != change.after {
+            changes.push(change);
+        }
+    }
+
+    changes
+}
+
+/// Matches each item in after to an item in before. Items are matched by id first, then by
+/// entry id in order. Returns the index in before for each item in after.
+fn match_by_ids<T>(
+    before: &[T],
+    after: &[T],
+    id: impl Fn(&T) -> &str,
+    entry_id: impl Fn(&T) -> &str,
+) -> Vec<Option<usize>> {
+    let mut matched: Vec<bool> = vec![false; before.len()];
+    let mut matches: Vec<Option<usize>> = vec![None; after.len()];
+
+    for (i, item) in after.iter().enumerate() {
+        if let Some(j) = (0..before.len()).find(|&j| !matched[j] && id(&before[j]) == id(item)) {
+            matched[j] = true;
+            matches[i] = Some(j);
+        }
+    }
+
+    for (i, item) in after.iter().enumerate() {
+        if matches[i].is_some() {
+            continue;
+        }
+        if let Some(j) =
+            (0..before.len()).find(|&j| !matched[j] && entry_id(&before[j]) == entry_id(item))
+        {
+            matched[j] = true;
+            matches[i] = Some(j);
+        }
+    }
+
+    matches
+}
+
+impl ItemChange {
+    /// Number added, number removed is negative.
+    pub fn difference(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+impl UnitDiff {
+    /// Compares two versions of a unit. A missing version is a unit that was added or removed.
+    pub fn from_units(before: Option<&Unit>, after: Option<&Unit>) -> Self {
+        let models = |unit: Option<&Unit>| match unit {
+            Some(unit) => unit.models.iter().map(|m| m.number as u32).sum(),
+            None => 0,
+        };
+        let weapons = |unit: Option<&Unit>| match unit {
+            Some(unit) => count_items(
+                unit.models
+                    .iter()
+                    .flat_map(|m| m.weapons.iter())
+                    .map(|w| (w.name.as_str(), w.number)),
+            ),
+            None => Vec::new(),
+        };
+        let wargear = |unit: Option<&Unit>| match unit {
+            Some(unit) => count_items(
+                unit.models
+                    .iter()
+                    .flat_map(|m| m.wargear.iter())
+                    .map(|w| (w.name.as_str(), w.number)),
+            ),
+            None => Vec::new(),
+        };
+        let points = |unit: Option<&Unit>| unit.map(|u| u.points).unwrap_or(0.0);
+        let power_level = |unit: Option<&Unit>| unit.map(|u| u.power_level).unwrap_or(0.0);
+
+        let (name, change) = match (before, after) {
+            (None, Some(unit)) => (unit.name.to_owned(), ChangeType::Added),
+            (Some(unit), None) => (unit.name.to_owned(), ChangeType::Removed),
+            (_, Some(unit)) => (unit.name.to_owned(), ChangeType::Changed),
+            (None, None) => (String::new(), ChangeType::Changed),
+        };
+
+        Self {
+            name,
+            change,
+            models_before: models(before),
+            models_after: models(after),
+            weapons: item_changes(&weapons(before), &weapons(after)),
+            wargear: item_changes(&wargear(before), &wargear(after)),
+            points: points(after) - points(before),
+            power_level: power_level(after) - power_level(before),
+        }
+    }
+
+    /// Checks if the unit is the same in both versions.
+    pub fn is_empty(&self) -> bool {
+        self.change == ChangeType::Changed
+            && self.models_before == self.models_after
+            && self.weapons.is_empty()
+            && self.wargear.is_empty()
+            && self.points == 0.0
+            && self.power_level == 0.0
+    }
+}
+
+impl DetachmentDiff {
+    /// Compares two versions of a detachment. Units are matched by selection id, then by
+    /// datasheet. Units that did not change are skipped.
+    pub fn from_detachments(before: Option<&Detachment>, after: Option<&Detachment>) -> Self {
+        let before_units: &[Unit] = before.map(|d| d.units.as_slice()).unwrap_or(&[]);
+        let after_units: &[Unit] = after.map(|d| d.units.as_slice()).unwrap_or(&[]);
+
+        let matches = match_by_ids(
+            before_units,
+            after_units,
+            |u| u.selection_id.as_str(),
+            |u| datasheet_id(&u.entry_id),
+        );
+
+        let mut units: Vec<UnitDiff> = Vec::new();
+        for (i, unit) in after_units.iter().enumerate() {
+            units.push(UnitDiff::from_units(
+                matches[i].map(|j| &before_units[j]),
+                Some(unit),
+            ));
+        }
+        for (j, unit) in before_units.iter().enumerate() {
+            if !matches.contains(&Some(j)) {
+                units.push(UnitDiff::from_units(Some(unit), None));
+            }
+        }
+        units.retain(|u| !u.is_empty());
+
+        let (name, change) = match (before, after) {
+            (None, Some(detachment)) => (detachment.name.to_owned(), ChangeType::Added),
+            (Some(detachment), None) => (detachment.name.to_owned(), ChangeType::Removed),
+            (_, Some(detachment)) => (detachment.name.to_owned(), ChangeType::Changed),
+            (None, None) => (String::new(), ChangeType::Changed),
+        };
+
+        let cp = |detachment: Option<&Detachment>| detachment.map(|d| d.cp).unwrap_or(0.0);
+
+        Self {
+            name,
+            change,
+            points: units.iter().map(|u| u.points).sum(),
+            power_level: units.iter().map(|u| u.power_level).sum(),
+            cp: cp(after) - cp(before),
+            units,
+        }
+    }
+
+    /// Checks if the detachment is the same in both versions.
+    pub fn is_empty(&self) -> bool {
+        self.change == ChangeType::Changed && self.units.is_empty() && self.cp == 0.0
+    }
+}
+
+impl ArmyDiff {
+    /// Compares two versions of an army. Detachments are matched by force id, then by
+    /// detachment type. Detachments that did not change are skipped.
+    pub fn from_armies(before: &Army, after: &Army) -> Self {
+        let matches = match_by_ids(
+            &before.detachments,
+            &after.detachments,
+            |d| d.force_id.as_str(),
+            |d| d.entry_id.as_str(),
+        );
+
+        let mut detachments: Vec<DetachmentDiff> = Vec::new();
+        for (i, detachment) in after.detachments.iter().enumerate() {
+            detachments.push(DetachmentDiff::from_detachments(
+                matches[i].map(|j| &before.detachments[j]),
+                Some(detachment),
+            ));
+        }
+        for (j, detachment) in before.detachments.iter().enumerate() {
+            if !matches.contains(&Some(j)) {
+                detachments.push(DetachmentDiff::from_detachments(Some(detachment), None));
+            }
+        }
+        detachments.retain(|d| !d.is_empty());
+
+        Self {
+            points: detachments.iter().map(|d| d.points).sum(),
+            power_level: detachments.iter().map(|d| d.power_level).sum(),
+            cp: after.cp - before.cp,
+            detachments,
+        }
+    }
+
+    /// Checks if both versions of the army are the same.
+    pub fn is_empty(&self) -> bool {
+        self.detachments.is_empty() && self.cp == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::{load_roster, load_unit};
+
+    fn sample_army() -> Army {
+        Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap()
+    }
+
+    #[test]
+    fn same_army_has_no_changes() {
+        let diff = ArmyDiff::from_armies(&sample_army(), &sample_army());
+
+        assert!(diff.is_empty());
+        assert_eq!((diff.points, diff.power_level, diff.cp), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn added_and_removed_units() {
+        let before = sample_army();
+        let mut after = sample_army();
+        let conscripts = after.detachments[0]
+            .units
+            .iter()
+            .rposition(|u| u.name == "Conscripts")
+            .unwrap();
+        after.detachments[0].units.remove(conscripts);
+        // The copy has the same selection id, so it is matched to the existing tank
+        after.detachments[0].units.push(load_unit(
+            "Astra_1000/Astra_1000.ros",
+            "Leman Russ Battle Tanks",
+        ));
+
+        let diff = ArmyDiff::from_armies(&before, &after);
+        assert_eq!(diff.detachments.len(), 1);
+        assert_eq!(diff.detachments[0].name, "Battalion Detachment 0CP");
+        assert_eq!(diff.points, 110.0);
+
+        let units = &diff.detachments[0].units;
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].name, "Leman Russ Battle Tanks");
+        assert_eq!(units[0].change, ChangeType::Added);
+        assert_eq!(units[0].points, 210.0);
+        assert_eq!((units[0].models_before, units[0].models_after), (0, 1));
+
+        assert_eq!(units[1].name, "Conscripts");
+        assert_eq!(units[1].change, ChangeType::Removed);
+        assert_eq!(units[1].points, -100.0);
+        assert_eq!((units[1].models_before, units[1].models_after), (20, 0));
+        let lasguns = units[1]
+            .weapons
+            .iter()
+            .find(|w| w.name == "Lasgun")
+            .unwrap();
+        assert_eq!(lasguns.difference(), -20);
+    }
+
+    #[test]
+    fn changed_weapon_count() {
+        let before = sample_army();
+        let mut after = sample_army();
+        let lasguns = after.detachments[0]
+            .units
+            .iter_mut()
+            .find(|u| u.name == "Conscripts")
+            .unwrap()
+            .models
+            .iter_mut()
+            .flat_map(|m| m.weapons.iter_mut())
+            .find(|w| w.name == "Lasgun")
+            .unwrap();
+        lasguns.number -= 2;
+
+        let diff = ArmyDiff::from_armies(&before, &after);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.points, 0.0);
+
+        let units = &diff.detachments[0].units;
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].change, ChangeType::Changed);
+        assert!(units[0].wargear.is_empty());
+        assert_eq!(
+            units[0].weapons,
+            [ItemChange {
+                name: "Lasgun".to_string(),
+                before: 20,
+                after: 18,
+            }]
+        );
+        assert_eq!(units[0].weapons[0].difference(), -2);
+    }
+}
@@ -0,0 +1,54 @@
+use super::keyword::{BattlefieldRole, Keywords};
+
+/// Edition of the game rules used for calculations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edition {
+    Ninth,
+    Tenth,
+}
+
+impl Edition {
+    /// Gets the edition from the roster's game system name (i.e. "Warhammer 40,000 9th Edition").
+    pub fn from_game_system(game_system_name: &str) -> Result<Self, String> {
+        if game_system_name.contains("9th") {
+            Ok(Edition::Ninth)
+        } else if game_system_name.contains("10th") {
+            Ok(Edition::Tenth)
+        } else {
+            Err("Unsupported game system: ".to_string() + game_system_name)
+        }
+    }
+
+    /// Hit rolls can never be modified by more than -1 or +1.
+    pub fn limit_hit_modifier(&self, modifier: i8) -> i8 {
+        modifier.clamp(-1, 1)
+    }
+
+    /// Wound rolls can never be modified by more than -1 or +1.
+    pub fn limit_wound_modifier(&self, modifier: i8) -> i8 {
+        modifier.clamp(-1, 1)
+    }
+
+    /// Most copies of a datasheet allowed in a matched play army (the Rule of Three).
+    /// 9th edition does not limit Troops and Dedicated Transports, 10th edition allows six
+    /// Battleline and Dedicated Transport units and one of each Epic Hero.
+    pub fn datasheet_limit(&self, role: Option<BattlefieldRole>, keywords: &Keywords) -> Option<u8> {
+        match self {
+            Edition::Ninth => match role {
+                Some(BattlefieldRole::Troops) | Some(BattlefieldRole::DedicatedTransport) => None,
+                _ => Some(3),
+            },
+            Edition::Tenth => {
+                if keywords.contains("Epic Hero") {
+                    Some(1)
+                } else if keywords.contains("Battleline")
+                    || role == Some(BattlefieldRole::DedicatedTransport)
+                {
+                    Some(6)
+                } else {
+                    Some(3)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use super::army::{Army, Detachment};
+
+/// Units with these keywords do not break faction requirements.
+const EXEMPT_KEYWORDS: [&str; 2] = ["Agent of the Imperium", "Unaligned"];
+
+/// Whether a rule's conditions are checked over the whole army or each detachment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleScope {
+    Army,
+    Detachment,
+}
+
+/// Condition that has to be met for an army rule to be active.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleCondition {
+    /// Every unit has the faction keyword (i.e. "Astra Militarum").
+    AllUnitsHaveKeyword(String),
+    /// Every unit with the placeholder keyword (i.e. "<REGIMENT>") is from the same subfaction.
+    SameSubfaction(String),
+}
+
+/// Army rule and the conditions it needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArmyRule {
+    pub name: String,
+    pub scope: RuleScope,
+    pub conditions: Vec<RuleCondition>,
+}
+
+/// Whether an army rule is active and why it was lost.
+#[derive(Debug, Clone)]
+pub struct RuleStatus {
+    pub name: String,
+    /// Detachment the rule is checked for, or None for the whole army.
+    pub detachment: Option<String>,
+    pub active: bool,
+    pub problems: Vec<String>,
+}
+
+impl ArmyRule {
+    /// Gets the conditions of a known army rule. Rules without faction requirements are None.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (scope, conditions) = match name {
+            "Hammer of the Emperor" => (
+                RuleScope::Army,
+                vec![
+                    RuleCondition::AllUnitsHaveKeyword("Astra Militarum".to_string()),
+                    RuleCondition::SameSubfaction("<REGIMENT>".to_string()),
+                ],
+            ),
+            "Regimental Doctrines" => (
+                RuleScope::Detachment,
+                vec![
+                    RuleCondition::AllUnitsHaveKeyword("Astra Militarum".to_string()),
+                    RuleCondition::SameSubfaction("<REGIMENT>".to_string()),
+                ],
+            ),
+            "Code Chivalric" => (
+                RuleScope::Detachment,
+                vec![RuleCondition::AllUnitsHaveKeyword("Imperial Knights".to_string())],
+            ),
+            "Noble Households" => (
+                RuleScope::Detachment,
+                vec![RuleCondition::SameSubfaction("<Noble Household>".to_string())],
+            ),
+            "Craftworld Attributes" => (
+                RuleScope::Detachment,
+                vec![
+                    RuleCondition::AllUnitsHaveKeyword("Asuryani".to_string()),
+                    RuleCondition::SameSubfaction("<CRAFTWORLD>".to_string()),
+                ],
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            name: name.to_owned(),
+            scope,
+            conditions,
+        })
+    }
+
+    /// Checks the conditions against the detachments and returns the problems found.
+    pub fn check(&self, detachments: &[&Detachment]) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+
+        for condition in &self.conditions {
+            match condition {
+                RuleCondition::AllUnitsHaveKeyword(keyword) => {
+                    for unit in detachments.iter().flat_map(|d| d.units.iter()) {
+                        if !unit.keywords.contains(keyword)
+                            && !EXEMPT_KEYWORDS.iter().any(|k| unit.keywords.contains(k))
+                        {
+                            problems.push(format!(
+                                "{} does not have the {} keyword",
+                                unit.name, keyword
+                            ));
+                        }
+                    }
+                }
+                RuleCondition::SameSubfaction(placeholder) => {
+                    // Only detachments with units that use the placeholder need a subfaction
+                    let mut subfactions: Vec<&str> = Vec::new();
+                    for detachment in detachments {
+                        if !detachment.units.iter().any(|u| u.keywords.contains(placeholder)) {
+                            continue;
+                        }
+
+                        match &detachment.config.subfaction {
+                            Some(subfaction) => {
+                                if !subfactions.contains(&subfaction.as_str()) {
+                                    subfactions.push(subfaction);
+                                }
+                            }
+                            None => problems.push(format!(
+                                "{} has {} units without a chosen subfaction",
+                                detachment.name, placeholder
+                            )),
+                        }
+                    }
+
+                    if subfactions.len() > 1 {
+                        problems.push(format!(
+                            "{} units are from different subfactions: {}",
+                            placeholder,
+                            subfactions.join(", ")
+                        ));
+                    }
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+/// Checks every army rule of the army's detachments. Rules without known conditions are active.
+pub fn check_army_rules(army: &Army) -> Vec<RuleStatus> {
+    let mut statuses: Vec<RuleStatus> = Vec::new();
+    let all_detachments: Vec<&Detachment> = army.detachments.iter().collect();
+
+    for detachment in &army.detachments {
+        for name in &detachment.rules {
+            let rule = ArmyRule::from_name(name);
+
+            // Army wide rules are only reported once
+            if rule.as_ref().map(|r| r.scope) == Some(RuleScope::Army)
+                && statuses.iter().any(|s| &s.name == name && s.detachment.is_none())
+            {
+                continue;
+            }
+
+            let (detachment_name, problems) = match &rule {
+                Some(rule) if rule.scope == RuleScope::Army => (None, rule.check(&all_detachments)),
+                Some(rule) => (Some(detachment.name.to_owned()), rule.check(&[detachment])),
+                None => (Some(detachment.name.to_owned()), Vec::new()),
+            };
+
+            statuses.push(RuleStatus {
+                name: name.to_owned(),
+                detachment: detachment_name,
+                active: problems.is_empty(),
+                problems,
+            });
+        }
+    }
+
+    statuses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::load_roster;
+
+    fn sample_army() -> Army {
+        Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap()
+    }
+
+    fn status<'a>(statuses: &'a [RuleStatus], name: &str) -> &'a RuleStatus {
+        statuses.iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[test]
+    fn known_army_rules() {
+        let hammer = ArmyRule::from_name("Hammer of the Emperor").unwrap();
+        assert_eq!(hammer.scope, RuleScope::Army);
+        assert_eq!(
+            hammer.conditions,
+            [
+                RuleCondition::AllUnitsHaveKeyword("Astra Militarum".to_string()),
+                RuleCondition::SameSubfaction("<REGIMENT>".to_string()),
+            ]
+        );
+
+        let code = ArmyRule::from_name("Code Chivalric").unwrap();
+        assert_eq!(code.scope, RuleScope::Detachment);
+
+        assert_eq!(ArmyRule::from_name("Defenders of Humanity"), None);
+    }
+
+    #[test]
+    fn knights_break_hammer_of_the_emperor() {
+        let statuses = check_army_rules(&sample_army());
+
+        let hammer = status(&statuses, "Hammer of the Emperor");
+        assert_eq!(hammer.detachment, None);
+        assert!(!hammer.active);
+        assert_eq!(
+            hammer.problems,
+            ["Armiger Helverins does not have the Astra Militarum keyword"]
+        );
+
+        // Rules of the Knights' own detachment are kept
+        let code = status(&statuses, "Code Chivalric");
+        assert_eq!(
+            code.detachment.as_deref(),
+            Some("Super-Heavy Auxiliary Detachment -3CP")
+        );
+        assert!(code.active);
+        assert!(status(&statuses, "Defenders of Humanity").active);
+    }
+
+    #[test]
+    fn agents_of_the_imperium_keep_hammer_of_the_emperor() {
+        let mut army = sample_army();
+        for unit in army.detachments[1].units.iter_mut() {
+            unit.keywords
+                .faction
+                .push("Agent of the Imperium".to_string());
+        }
+
+        let statuses = check_army_rules(&army);
+        let hammer = status(&statuses, "Hammer of the Emperor");
+        assert!(hammer.active, "{:?}", hammer.problems);
+    }
+
+    #[test]
+    fn regiments_have_to_match() {
+        let mut army = sample_army();
+        army.detachments[1].units.clear();
+        army.detachments[0].config.subfaction = None;
+
+        let statuses = check_army_rules(&army);
+        assert_eq!(
+            status(&statuses, "Hammer of the Emperor").problems,
+            ["Battalion Detachment 0CP has <REGIMENT> units without a chosen subfaction"]
+        );
+    }
+}
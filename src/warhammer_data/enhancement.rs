@@ -0,0 +1,164 @@
+use super::ability::parse_abilities;
+use super::cost::get_cost;
+use super::ros_parser::ros_parser::Selection;
+
+/// Warlord trait or relic bought for a character.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enhancement {
+    pub name: String,
+    /// Rules text from the selection's abilities.
+    pub description: String,
+    /// CP cost as it is in the roster, so CP that is spent is negative.
+    pub cp_cost: f32,
+}
+
+impl Enhancement {
+    pub fn from_selection(selection: &Selection) -> Self {
+        let description: Vec<String> = parse_abilities(selection)
+            .into_iter()
+            .map(|ability| ability.value)
+            .collect();
+
+        Self {
+            name: selection.name.to_owned(),
+            description: description.join("\n"),
+            cp_cost: get_cost(&selection.costs, "CP"),
+        }
+    }
+}
+
+/// Checks if the selection makes the unit the Warlord.
+fn is_warlord(selection: &Selection) -> bool {
+    selection.name == "Warlord"
+        || match &selection.categories {
+            Some(categories) => selection.r#type == "upgrade"
+                && categories.categories.iter().any(|c| c.name == "Warlord"),
+            None => false,
+        }
+}
+
+/// Checks if the selection only has abilities (i.e. not a weapon or a model).
+fn has_only_abilities(selection: &Selection) -> bool {
+    match &selection.profiles {
+        Some(profiles) => profiles.profiles.iter().all(|p| p.type_name == "Abilities"),
+        None => false,
+    }
+}
+
+/// Checks if the selection is a warlord trait (i.e. "WT (Cadia): Superior Tactical Training").
+/// Traits without the prefix are found from their rules mentioning the Warlord, which is only
+/// checked for the Warlord's own selections as other upgrades can mention the Warlord too.
+pub fn is_warlord_trait(selection: &Selection, warlord: bool) -> bool {
+    if selection.name.starts_with("WT") || selection.name.contains("Warlord Trait") {
+        return true;
+    }
+
+    warlord
+        && has_only_abilities(selection)
+        && parse_abilities(selection)
+            .iter()
+            .any(|ability| ability.value.to_lowercase().contains("warlord"))
+}
+
+/// Checks if the selection is a relic (i.e. "Relic: The Blade of Conquest").
+pub fn is_relic(selection: &Selection) -> bool {
+    selection.name.starts_with("Relic")
+}
+
+/// Checks if the unit or any of its models is the Warlord.
+pub fn parse_warlord(selection: &Selection) -> bool {
+    is_warlord(selection)
+        || selection
+            .selections
+            .iter()
+            .flat_map(|s| s.selections.iter())
+            .any(parse_warlord)
+}
+
+/// Gets the warlord trait from the selections of a Warlord.
+pub fn parse_warlord_trait(selection: &Selection) -> Option<Enhancement> {
+    if !parse_warlord(selection) {
+        return None;
+    }
+
+    find_selection(selection, &|s| is_warlord_trait(s, true)).map(Enhancement::from_selection)
+}
+
+/// Gets the relic from the selection or any of its selections.
+pub fn parse_relic(selection: &Selection) -> Option<Enhancement> {
+    find_selection(selection, &is_relic).map(Enhancement::from_selection)
+}
+
+/// Finds the first upgrade selection that matches.
+fn find_selection<'a>(
+    selection: &'a Selection,
+    matches: &dyn Fn(&Selection) -> bool,
+) -> Option<&'a Selection> {
+    for selection in selection.selections.iter().flat_map(|s| s.selections.iter()) {
+        if selection.r#type == "upgrade" && matches(selection) {
+            return Some(selection);
+        }
+        if let Some(found) = find_selection(selection, matches) {
+            return Some(found);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::army::Army;
+    use crate::warhammer_data::load_roster;
+    use crate::warhammer_data::ros_parser::ros_parser::Roster;
+
+    fn find_selection_named<'a>(roster: &'a Roster, name: &str) -> &'a Selection {
+        roster
+            .forces
+            .forces
+            .iter()
+            .flat_map(|f| f.selections.selections.iter())
+            .find(|s| s.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn warlord_rules_only_make_a_trait_for_the_warlord() {
+        let roster = load_roster("Astra_1000/Astra_1000.ros");
+        // Whiteshields mention the Warlord in their rules
+        let whiteshields = find_selection_named(&roster, "Whiteshields");
+        assert!(!is_warlord_trait(whiteshields, false));
+        assert!(is_warlord_trait(whiteshields, true));
+
+        let conscripts = find_selection_named(&roster, "Conscripts");
+        assert_eq!(parse_warlord_trait(conscripts), None);
+
+        let creed =
+            parse_warlord_trait(find_selection_named(&roster, "Lord Castellan Creed")).unwrap();
+        assert_eq!(creed.name, "WT (Cadia): Superior Tactical Training");
+        assert_eq!(creed.cp_cost, 0.0);
+    }
+
+    #[test]
+    fn warlord_trait_without_prefix_is_found_on_the_warlord() {
+        let army = Army::from_roster(&load_roster("Test_Roster.ros")).unwrap();
+        let yvraine = army
+            .detachments
+            .iter()
+            .flat_map(|d| d.units.iter())
+            .find(|u| u.name == "Yvraine")
+            .unwrap();
+
+        assert!(yvraine.warlord);
+        assert_eq!(
+            yvraine.warlord_trait.as_ref().unwrap().name,
+            "Ynnari: Warden of Souls"
+        );
+        assert!(yvraine
+            .models
+            .iter()
+            .flat_map(|m| m.wargear.iter())
+            .all(|w| w.name != "Ynnari: Warden of Souls"));
+    }
+}
@@ -0,0 +1,120 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::ability::Ability;
+use super::profile::ProfileValue;
+use super::ros_parser::ros_parser::Profile;
+
+/// What happens when a model is destroyed and explodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explosion {
+    pub name: String,
+    /// D6 roll needed for the model to explode.
+    pub dice_roll: u8,
+    /// Distance in inches that units suffer mortal wounds within.
+    pub distance: u8,
+    pub mortal_wounds: ProfileValue,
+}
+
+impl Explosion {
+    /// Parses an "Explosion" profile (i.e. "Explodes (Armiger)").
+    pub fn from_profile(profile: &Profile) -> Result<Self, String> {
+        lazy_static! {
+            // Regex to get the number from a roll or distance (i.e. "6+" or "6"")
+            static ref NUMBER_RE: Regex = Regex::new(r"(\d+)").unwrap();
+        }
+
+        let mut explosion = Self {
+            name: profile.name.to_owned(),
+            dice_roll: 0,
+            distance: 0,
+            mortal_wounds: ProfileValue {
+                dice_value: None,
+                flat_value: None,
+            },
+        };
+
+        for characteristic in &profile.characteristics.characteristics {
+            let value = characteristic.value.as_deref().unwrap_or("");
+            match characteristic.name.as_str() {
+                "Dice Roll" => {
+                    explosion.dice_roll = NUMBER_RE
+                        .captures(value)
+                        .map(|captures| captures[1].parse().unwrap())
+                        .ok_or("Explosion dice roll is not a number: ".to_string() + value)?
+                }
+                "Distance" => {
+                    explosion.distance = NUMBER_RE
+                        .captures(value)
+                        .map(|captures| captures[1].parse().unwrap())
+                        .ok_or("Explosion distance is not a number: ".to_string() + value)?
+                }
+                "Mortal Wounds" => explosion.mortal_wounds = ProfileValue::from_str(value),
+                _ => {
+                    return Err("Unknown characteristic for explosion: ".to_string()
+                        + characteristic.name.as_str())
+                }
+            }
+        }
+
+        Ok(explosion)
+    }
+
+    /// Parses an explosion from ability text
+    /// (i.e. "On a 6 it explodes, and each unit within 6" suffers D3 mortal wounds").
+    pub fn from_ability(ability: &Ability) -> Option<Self> {
+        lazy_static! {
+            static ref EXPLODES_RE: Regex = Regex::new(
+                r#"(?i)on a (\d)\+? it explodes,? and each unit within (\d+)(?:"|''| inches) suffers (\S+) mortal wounds?"#
+            )
+            .unwrap();
+        }
+
+        let captures = EXPLODES_RE.captures(&ability.value)?;
+
+        Some(Self {
+            name: ability.name.to_owned(),
+            dice_roll: captures[1].parse().unwrap(),
+            distance: captures[2].parse().unwrap(),
+            mortal_wounds: ProfileValue::from_str(&captures[3]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::warhammer_data::load_unit;
+    use crate::warhammer_data::profile::ProfileValue;
+
+    #[test]
+    fn explosion_from_profile() {
+        let armigers = load_unit("Astra_1000/Astra_1000.ros", "Armiger Helverins");
+
+        for model in &armigers.models {
+            let explosion = model.explosion.as_ref().unwrap();
+            assert_eq!(explosion.name, "Explodes (Armiger)");
+            assert_eq!((explosion.dice_roll, explosion.distance), (6, 6));
+            assert_eq!(explosion.mortal_wounds, ProfileValue::from_str("D3"));
+        }
+    }
+
+    #[test]
+    fn explosion_from_ability() {
+        let leman_russ = load_unit("Astra_1000/Astra_1000.ros", "Leman Russ Battle Tanks");
+
+        let explosion = leman_russ.models[0].explosion.as_ref().unwrap();
+        assert_eq!(explosion.name, "Explodes");
+        assert_eq!((explosion.dice_roll, explosion.distance), (6, 6));
+        assert_eq!(explosion.mortal_wounds, ProfileValue::from_str("D3"));
+
+        let yncarne = load_unit("Test_Roster.ros", "The Yncarne");
+        let explosion = yncarne.models[0].explosion.as_ref().unwrap();
+        assert_eq!(explosion.name, "Explosion of Soul Energy");
+        assert_eq!((explosion.dice_roll, explosion.distance), (6, 3));
+        assert_eq!(explosion.mortal_wounds, ProfileValue::from_str("1"));
+
+        // Infantry do not explode
+        let conscripts = load_unit("Astra_1000/Astra_1000.ros", "Conscripts");
+        assert!(conscripts.models.iter().all(|m| m.explosion.is_none()));
+    }
+}
@@ -0,0 +1,224 @@
+use super::ros_parser::ros_parser::Category;
+
+/// Keywords that say what type of unit it is.
+const UNIT_TYPE_KEYWORDS: [&str; 20] = [
+    "Aircraft",
+    "Beast",
+    "Bike",
+    "Biker",
+    "Building",
+    "Cavalry",
+    "Character",
+    "Core",
+    "Daemon",
+    "Fly",
+    "Infantry",
+    "Jet Pack",
+    "Jump Pack",
+    "Monster",
+    "Mounted",
+    "Psyker",
+    "Swarm",
+    "Titanic",
+    "Transport",
+    "Vehicle",
+];
+
+/// Categories that are used by BattleScribe and are not keywords.
+const IGNORED_CATEGORIES: [&str; 3] = ["Configuration", "Stratagems", "Uncategorised"];
+
+/// Force organisation slot that a unit takes in a detachment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BattlefieldRole {
+    HQ,
+    Troops,
+    Elites,
+    FastAttack,
+    HeavySupport,
+    Flyer,
+    DedicatedTransport,
+    LordOfWar,
+    Fortification,
+    NoForceOrgSlot,
+}
+
+/// Keywords of a unit split by what they are used for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keywords {
+    /// Faction keywords without the "Faction: " prefix (i.e. "Astra Militarum").
+    pub faction: Vec<String>,
+    /// Keywords for the type of unit (i.e. "Infantry" or "Vehicle").
+    pub unit_type: Vec<String>,
+    /// Every other keyword (i.e. "Leman Russ" or "Warlord").
+    pub other: Vec<String>,
+}
+
+impl BattlefieldRole {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "HQ" => Some(BattlefieldRole::HQ),
+            "Troops" => Some(BattlefieldRole::Troops),
+            "Elites" => Some(BattlefieldRole::Elites),
+            "Fast Attack" => Some(BattlefieldRole::FastAttack),
+            "Heavy Support" => Some(BattlefieldRole::HeavySupport),
+            "Flyer" => Some(BattlefieldRole::Flyer),
+            "Dedicated Transport" => Some(BattlefieldRole::DedicatedTransport),
+            "Lord of War" => Some(BattlefieldRole::LordOfWar),
+            "Fortification" => Some(BattlefieldRole::Fortification),
+            "No Force Org Slot" => Some(BattlefieldRole::NoForceOrgSlot),
+            _ => None,
+        }
+    }
+
+    /// Gets the role from the primary category, or the first category that is a role.
+    pub fn from_categories(categories: &[Category]) -> Option<Self> {
+        categories
+            .iter()
+            .filter(|c| c.primary)
+            .find_map(|c| Self::from_name(&c.name))
+            .or_else(|| categories.iter().find_map(|c| Self::from_name(&c.name)))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BattlefieldRole::HQ => "HQ",
+            BattlefieldRole::Troops => "Troops",
+            BattlefieldRole::Elites => "Elites",
+            BattlefieldRole::FastAttack => "Fast Attack",
+            BattlefieldRole::HeavySupport => "Heavy Support",
+            BattlefieldRole::Flyer => "Flyer",
+            BattlefieldRole::DedicatedTransport => "Dedicated Transport",
+            BattlefieldRole::LordOfWar => "Lord of War",
+            BattlefieldRole::Fortification => "Fortification",
+            BattlefieldRole::NoForceOrgSlot => "No Force Org Slot",
+        }
+    }
+}
+
+impl Keywords {
+    /// Splits category names into keywords. Battlefield roles and BattleScribe categories are skipped.
+    pub fn from_names(names: &[String]) -> Self {
+        let mut keywords = Self::default();
+
+        for name in names {
+            if BattlefieldRole::from_name(name).is_some()
+                || IGNORED_CATEGORIES.contains(&name.as_str())
+            {
+                continue;
+            }
+
+            let (list, keyword) = if let Some(faction) = name.strip_prefix("Faction: ") {
+                (&mut keywords.faction, faction)
+            } else if UNIT_TYPE_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(name)) {
+                (&mut keywords.unit_type, name.as_str())
+            } else {
+                (&mut keywords.other, name.as_str())
+            };
+
+            if !list.iter().any(|k| k == keyword) {
+                list.push(keyword.to_owned());
+            }
+        }
+
+        keywords
+    }
+
+    /// Every keyword, with faction keywords first.
+    pub fn all(&self) -> Vec<String> {
+        self.faction
+            .iter()
+            .chain(self.unit_type.iter())
+            .chain(self.other.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Checks for a keyword of any type, ignoring case.
+    pub fn contains(&self, keyword: &str) -> bool {
+        self.faction
+            .iter()
+            .chain(self.unit_type.iter())
+            .chain(self.other.iter())
+            .any(|k| k.eq_ignore_ascii_case(keyword))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::load_unit;
+
+    fn category(name: &str, primary: bool) -> Category {
+        Category {
+            id: String::new(),
+            name: name.to_string(),
+            entry_id: String::new(),
+            primary,
+        }
+    }
+
+    #[test]
+    fn role_from_categories() {
+        // The primary category is used over other roles
+        let categories = [
+            category("Troops", false),
+            category("Infantry", false),
+            category("Elites", true),
+        ];
+        assert_eq!(
+            BattlefieldRole::from_categories(&categories),
+            Some(BattlefieldRole::Elites)
+        );
+
+        // Otherwise the first role is used
+        let categories = [category("Infantry", true), category("Fast Attack", false)];
+        assert_eq!(
+            BattlefieldRole::from_categories(&categories),
+            Some(BattlefieldRole::FastAttack)
+        );
+
+        assert_eq!(
+            BattlefieldRole::from_categories(&[category("Infantry", true)]),
+            None
+        );
+
+        for role in [BattlefieldRole::LordOfWar, BattlefieldRole::NoForceOrgSlot] {
+            assert_eq!(BattlefieldRole::from_name(role.name()), Some(role));
+        }
+    }
+
+    #[test]
+    fn keywords_are_split_by_type() {
+        let conscripts = load_unit("Astra_1000/Astra_1000.ros", "Conscripts");
+
+        assert_eq!(conscripts.role, Some(BattlefieldRole::Troops));
+        let mut faction = conscripts.keywords.faction.clone();
+        faction.sort();
+        assert_eq!(faction, ["<REGIMENT>", "Astra Militarum", "Imperium"]);
+        assert_eq!(conscripts.keywords.unit_type, ["Infantry"]);
+        // Whiteshields is a keyword from the unit's upgrade
+        assert_eq!(conscripts.keywords.other, ["Conscript", "Whiteshields"]);
+
+        assert!(conscripts.keywords.contains("INFANTRY"));
+        assert!(conscripts.keywords.contains("astra militarum"));
+        assert!(!conscripts.keywords.contains("Troops"));
+    }
+
+    #[test]
+    fn roles_and_roster_categories_are_not_keywords() {
+        let names: Vec<String> = [
+            "HQ",
+            "Stratagems",
+            "Faction: Cadian",
+            "Character",
+            "Officer",
+        ]
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+
+        let keywords = Keywords::from_names(&names);
+
+        assert_eq!(keywords.all(), ["Cadian", "Character", "Officer"]);
+    }
+}
@@ -0,0 +1,296 @@
+use super::config::BattleSize;
+use super::cost::{get_cost, sum_costs};
+use super::ros_parser::ros_parser::{Roster, Selection};
+use super::validation::DetachmentType;
+
+/// Why Command Points were gained or spent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LedgerEntryType {
+    /// Starting CP from the battle size.
+    BattleSize,
+    /// Command cost of a detachment (i.e. "Detachment Command Cost").
+    DetachmentCost,
+    /// Pre-game stratagems (i.e. extra relics or warlord traits).
+    Stratagem,
+    /// CP given back (i.e. for the Warlord's detachment).
+    Refund,
+    Other,
+}
+
+/// A selection in the roster that gains or spends Command Points.
+#[derive(Debug, Clone)]
+pub struct LedgerEntry {
+    pub name: String,
+    pub detachment: String,
+    /// Unit the selection was made for, if it was not made for the detachment.
+    pub unit: Option<String>,
+    pub entry_type: LedgerEntryType,
+    /// CP gained, CP spent is negative.
+    pub cp: f32,
+}
+
+/// Every Command Point gained and spent in a roster.
+#[derive(Debug, Clone)]
+pub struct CpLedger {
+    pub entries: Vec<LedgerEntry>,
+    /// Names of every detachment in the roster, including those without entries.
+    pub detachments: Vec<String>,
+    /// Total CP from the roster's costs.
+    pub roster_cp: f32,
+}
+
+/// Checks if the selection or its categories are a stratagem.
+fn is_stratagem(selection: &Selection) -> bool {
+    selection.name.starts_with("Stratagem")
+        || match &selection.categories {
+            Some(categories) => categories.categories.iter().any(|c| c.name == "Stratagems"),
+            None => false,
+        }
+}
+
+impl CpLedger {
+    pub fn from_roster(roster: &Roster) -> Self {
+        let mut entries: Vec<LedgerEntry> = Vec::new();
+        let mut detachments: Vec<String> = Vec::new();
+
+        for force in &roster.forces.forces {
+            detachments.push(force.name.to_owned());
+            for selection in &force.selections.selections {
+                // Selections that are not units (i.e. "Battle Size") are made for the detachment
+                let unit = match selection.r#type.as_str() {
+                    "model" | "unit" => Some(selection.name.to_owned()),
+                    _ => None,
+                };
+                Self::add_entries(&mut entries, selection, &force.name, &unit, false);
+            }
+        }
+
+        Self {
+            entries,
+            detachments,
+            roster_cp: sum_costs(&roster.costs, "CP"),
+        }
+    }
+
+    /// Adds an entry for the selection and all of its selections that have a CP cost.
+    fn add_entries(
+        entries: &mut Vec<LedgerEntry>,
+        selection: &Selection,
+        detachment: &str,
+        unit: &Option<String>,
+        in_battle_size: bool,
+    ) {
+        let in_battle_size = in_battle_size || selection.name == "Battle Size";
+        let cp = get_cost(&selection.costs, "CP");
+
+        if cp != 0.0 {
+            let entry_type = if in_battle_size {
+                LedgerEntryType::BattleSize
+            } else if selection.name == "Detachment Command Cost" {
+                LedgerEntryType::DetachmentCost
+            } else if is_stratagem(selection) {
+                LedgerEntryType::Stratagem
+            } else if cp > 0.0 {
+                LedgerEntryType::Refund
+            } else {
+                LedgerEntryType::Other
+            };
+
+            entries.push(LedgerEntry {
+                name: selection.name.trim().to_owned(),
+                detachment: detachment.to_owned(),
+                unit: unit.to_owned(),
+                entry_type,
+                cp,
+            });
+        }
+
+        for selection in selection.selections.iter().flat_map(|s| s.selections.iter()) {
+            Self::add_entries(entries, selection, detachment, unit, in_battle_size);
+        }
+    }
+
+    /// Sum of the entries of a type.
+    pub fn total_of(&self, entry_type: LedgerEntryType) -> f32 {
+        self.entries
+            .iter()
+            .filter(|e| e.entry_type == entry_type)
+            .map(|e| e.cp)
+            .sum()
+    }
+
+    /// Battle size chosen in the roster.
+    pub fn battle_size(&self) -> Option<BattleSize> {
+        self.entries
+            .iter()
+            .filter(|e| e.entry_type == LedgerEntryType::BattleSize)
+            .find_map(|e| BattleSize::from_name(&e.name).ok())
+    }
+
+    /// CP the army starts with from the rules for its battle size, not the roster's CP.
+    pub fn starting_cp(&self) -> f32 {
+        match self.battle_size() {
+            Some(battle_size) => battle_size.starting_cp() as f32,
+            None => 0.0,
+        }
+    }
+
+    /// CP left after every entry.
+    pub fn total(&self) -> f32 {
+        self.entries.iter().map(|e| e.cp).sum()
+    }
+
+    /// CP in the roster that is not explained by the entries.
+    pub fn difference(&self) -> f32 {
+        self.roster_cp - self.total()
+    }
+
+    /// Checks the battle size and detachment costs against the rules, and the entries against
+    /// the roster's CP.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+
+        match self.battle_size() {
+            Some(battle_size) => {
+                let gained = self.total_of(LedgerEntryType::BattleSize);
+                if gained != self.starting_cp() {
+                    problems.push(format!(
+                        "{} gives {}CP, should be {}CP",
+                        battle_size.name,
+                        gained,
+                        self.starting_cp()
+                    ));
+                }
+            }
+            None => problems.push("Roster does not have a battle size".to_string()),
+        }
+
+        for detachment in &self.detachments {
+            let detachment_type = match DetachmentType::from_name(detachment) {
+                Some(detachment_type) => detachment_type,
+                None => {
+                    problems.push("Unknown detachment type: ".to_string() + detachment);
+                    continue;
+                }
+            };
+
+            let spent: f32 = -self
+                .entries
+                .iter()
+                .filter(|e| {
+                    &e.detachment == detachment && e.entry_type == LedgerEntryType::DetachmentCost
+                })
+                .map(|e| e.cp)
+                .sum::<f32>();
+            if spent != detachment_type.command_cost() as f32 {
+                problems.push(format!(
+                    "{} costs {}CP, should be {}CP",
+                    detachment,
+                    spent,
+                    detachment_type.command_cost()
+                ));
+            }
+        }
+
+        if self.difference().abs() >= 0.01 {
+            problems.push(format!(
+                "Roster has {}CP, the selections add up to {}CP",
+                self.roster_cp,
+                self.total()
+            ));
+        }
+
+        problems
+    }
+
+    /// Checks if the roster's CP agrees with the rules and its selections.
+    pub fn reconciles(&self) -> bool {
+        self.problems().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::load_roster;
+
+    /// Changes the CP cost of the first selection in a force with the name.
+    fn set_cp(roster: &mut Roster, name: &str, cp: f32) {
+        fn set(selection: &mut Selection, name: &str, cp: f32) -> bool {
+            if selection.name.trim() == name {
+                if let Some(costs) = &mut selection.costs {
+                    for cost in costs.costs.iter_mut().filter(|c| c.name == "CP") {
+                        cost.value = cp;
+                    }
+                }
+                return true;
+            }
+            selection
+                .selections
+                .iter_mut()
+                .flat_map(|s| s.selections.iter_mut())
+                .any(|s| set(s, name, cp))
+        }
+
+        assert!(roster
+            .forces
+            .forces
+            .iter_mut()
+            .flat_map(|f| f.selections.selections.iter_mut())
+            .any(|s| set(s, name, cp)));
+    }
+
+    #[test]
+    fn sample_rosters_reconcile() {
+        let ledger = CpLedger::from_roster(&load_roster("Astra_1000/Astra_1000.ros"));
+        assert_eq!(ledger.problems(), Vec::<String>::new());
+        assert_eq!(ledger.starting_cp(), 6.0);
+        assert_eq!(ledger.total_of(LedgerEntryType::DetachmentCost), -3.0);
+        assert_eq!(ledger.total_of(LedgerEntryType::Stratagem), -3.0);
+        assert_eq!(ledger.total(), 2.0);
+
+        let ledger = CpLedger::from_roster(&load_roster("Test_Roster.ros"));
+        assert!(ledger.reconciles());
+        assert_eq!(ledger.starting_cp(), 12.0);
+        assert_eq!(ledger.total(), 11.0);
+    }
+
+    #[test]
+    fn hand_edited_cp_does_not_reconcile() {
+        let mut roster = load_roster("Astra_1000/Astra_1000.ros");
+        // The roster's total is edited as well, so only the rules can catch it
+        set_cp(
+            &mut roster,
+            "2. Incursion (51-100 Total PL / 501-1000 Points)",
+            9.0,
+        );
+        // Makes the free Battalion cost CP
+        set_cp(&mut roster, "Detachment Command Cost", -1.0);
+        for cost in roster.costs.costs.iter_mut().filter(|c| c.name == "CP") {
+            cost.value = 4.0;
+        }
+
+        let ledger = CpLedger::from_roster(&roster);
+        assert!(!ledger.reconciles());
+        assert_eq!(
+            ledger.problems(),
+            [
+                "2. Incursion (51-100 Total PL / 501-1000 Points) gives 9CP, should be 6CP",
+                "Battalion Detachment 0CP costs 1CP, should be 0CP",
+            ]
+        );
+    }
+
+    #[test]
+    fn roster_total_has_to_match_the_entries() {
+        let mut roster = load_roster("Astra_1000/Astra_1000.ros");
+        for cost in roster.costs.costs.iter_mut().filter(|c| c.name == "CP") {
+            cost.value = 5.0;
+        }
+
+        assert_eq!(
+            CpLedger::from_roster(&roster).problems(),
+            ["Roster has 5CP, the selections add up to 2CP"]
+        );
+    }
+}
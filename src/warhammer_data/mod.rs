@@ -1,7 +1,49 @@
 pub mod ability;
 pub mod army;
+pub mod combat;
+#[allow(dead_code)]
+pub mod config;
+#[allow(dead_code)]
+pub mod cost;
+pub mod defence;
+#[allow(dead_code)]
+pub mod diff;
+pub mod edition;
+#[allow(dead_code)]
+pub mod eligibility;
+pub mod enhancement;
+pub mod explosion;
+#[allow(dead_code)]
+pub mod keyword;
+#[allow(dead_code)]
+pub mod ledger;
 pub mod profile;
+pub mod psychic;
+pub mod simulation;
+#[allow(dead_code)]
+pub mod stats;
+pub mod transport;
 pub mod unit;
+#[allow(dead_code)]
+pub mod validation;
 pub mod weapon;
 
-pub mod ros_parser;
\ No newline at end of file
+pub mod ros_parser;
+
+/// Loads one of the rosters in the repository (i.e. "Astra_1000/Astra_1000.ros").
+#[cfg(test)]
+pub fn load_roster(path: &str) -> ros_parser::ros_parser::Roster {
+    let file = std::fs::File::open(path).unwrap();
+    serde_xml_rs::from_reader(std::io::BufReader::new(file)).unwrap()
+}
+
+/// Gets a unit by its name from one of the rosters in the repository.
+#[cfg(test)]
+pub fn load_unit(path: &str, name: &str) -> unit::Unit {
+    let army = army::Army::from_roster(&load_roster(path)).unwrap();
+    army.detachments
+        .into_iter()
+        .flat_map(|d| d.units)
+        .find(|u| u.name == name)
+        .unwrap()
+}
@@ -1,22 +1,35 @@
 use lazy_static::lazy_static;
+use rand::Rng;
 use regex::Regex;
 
+use super::ros_parser::ros_parser::{Profile, Selection};
+
 /// Types of dice used in the game.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DiceType {
     D3,
     D6,
 }
 
+impl DiceType {
+    /// Number of sides on the dice.
+    pub fn sides(&self) -> u8 {
+        match self {
+            DiceType::D3 => 3,
+            DiceType::D6 => 6,
+        }
+    }
+}
+
 /// DiceRoll holds information on how many and what type of dice to roll.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DiceRoll {
     pub number: u8,
     pub dice_type: DiceType,
 }
 
 /// Value for characteristics that can be a flat value, dice value or both.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ProfileValue {
     pub dice_value: Option<DiceRoll>,
     pub flat_value: Option<u8>,
@@ -66,4 +79,106 @@ impl ProfileValue {
             flat_value,
         }
     }
+
+    /// Multiplies both the number of dice and the flat value, i.e. "D6" x2 becomes "2D6".
+    /// Values that do not fit are capped at the largest value.
+    pub fn multiply(&self, factor: u8) -> Self {
+        Self {
+            dice_value: self.dice_value.map(|dice| DiceRoll {
+                number: dice.number.saturating_mul(factor),
+                dice_type: dice.dice_type,
+            }),
+            flat_value: self.flat_value.map(|flat| flat.saturating_mul(factor)),
+        }
+    }
+
+    /// Average result of the value.
+    pub fn average(&self) -> f32 {
+        let dice_average = match self.dice_value {
+            Some(dice) => dice.number as f32 * (dice.dice_type.sides() as f32 + 1.0) / 2.0,
+            None => 0.0,
+        };
+
+        dice_average + self.flat_value.unwrap_or(0) as f32
+    }
+
+    /// Probability of each result of the value, indexed by the result.
+    pub fn distribution(&self) -> Vec<f32> {
+        let mut distribution: Vec<f32> = vec![1.0];
+
+        // Add one dice at a time to the distribution
+        if let Some(dice) = self.dice_value {
+            let sides = dice.dice_type.sides() as usize;
+            for _ in 0..dice.number {
+                let mut next = vec![0.0; distribution.len() + sides];
+                for (value, chance) in distribution.iter().enumerate() {
+                    for roll in 1..=sides {
+                        next[value + roll] += chance / sides as f32;
+                    }
+                }
+                distribution = next;
+            }
+        }
+
+        // Shift the distribution by the flat value
+        let flat = self.flat_value.unwrap_or(0) as usize;
+        let mut shifted = vec![0.0; flat];
+        shifted.append(&mut distribution);
+
+        shifted
+    }
+
+    /// Rolls the dice for the value and adds the flat value. Results are capped at the largest value.
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> u8 {
+        let mut result = self.flat_value.unwrap_or(0);
+        if let Some(dice) = self.dice_value {
+            for _ in 0..dice.number {
+                result = result.saturating_add(rng.gen_range(1..=dice.dice_type.sides()));
+            }
+        }
+
+        result
+    }
+}
+
+/// Gets the first profile of a type from the selection or any of its selections.
+pub fn find_profile<'a>(selection: &'a Selection, type_name: &str) -> Option<&'a Profile> {
+    let profile = selection
+        .profiles
+        .iter()
+        .flat_map(|p| p.profiles.iter())
+        .find(|p| p.type_name == type_name);
+
+    profile.or_else(|| {
+        selection
+            .selections
+            .iter()
+            .flat_map(|s| s.selections.iter())
+            .find_map(|s| find_profile(s, type_name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn multiply_multiplies_dice_and_flat_value() {
+        let value = ProfileValue::from_str("D6+1").multiply(3);
+
+        assert_eq!(value.dice_value.unwrap().number, 3);
+        assert_eq!(value.flat_value, Some(3));
+        assert_eq!(value.average(), 13.5);
+    }
+
+    #[test]
+    fn multiply_saturates_instead_of_overflowing() {
+        let value = ProfileValue::from_str("2D6+100").multiply(200);
+
+        assert_eq!(value.dice_value.unwrap().number, u8::MAX);
+        assert_eq!(value.flat_value, Some(u8::MAX));
+        assert_eq!(value.roll(&mut StdRng::seed_from_u64(0)), u8::MAX);
+    }
 }
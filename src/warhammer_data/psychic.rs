@@ -0,0 +1,235 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::profile::find_profile;
+use super::ros_parser::ros_parser::{Profile, Selection};
+
+/// Psychic abilities of a model from its "Psyker" profile.
+#[derive(Debug, Clone)]
+pub struct Psyker {
+    pub cast: u8,
+    pub deny: u8,
+    pub powers_known: String,
+    pub discipline: Option<String>,
+    pub other: String,
+}
+
+/// Type of psychic power from the selection name (i.e. "1. Gaze of Ynnead (Witchfire)").
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PsychicPowerType {
+    Witchfire,
+    Blessing,
+    Malediction,
+}
+
+/// Psychic power from a "Psychic Power" profile.
+#[derive(Debug, Clone)]
+pub struct PsychicPower {
+    pub name: String,
+    pub power_type: Option<PsychicPowerType>,
+    pub warp_charge: u8,
+    pub range: Option<u8>,
+    pub details: String,
+}
+
+impl Psyker {
+    pub fn from_profile(profile: &Profile) -> Result<Self, String> {
+        let mut psyker = Self {
+            cast: 0,
+            deny: 0,
+            powers_known: "".to_string(),
+            discipline: None,
+            other: "".to_string(),
+        };
+
+        lazy_static! {
+            // Regex to get the discipline from powers known (i.e. "All of Revenant" or "2 - Revenant")
+            static ref DISCIPLINE_RE: Regex = Regex::new(r"(?:of|-) (?:the )?(.+)$").unwrap();
+        }
+
+        for characteristic in &profile.characteristics.characteristics {
+            let value = characteristic.value.as_deref().unwrap_or("");
+            match characteristic.name.as_str() {
+                "Cast" => {
+                    psyker.cast = value
+                        .parse()
+                        .map_err(|_| "Psyker cast is not a number: ".to_string() + value)?
+                }
+                "Deny" => {
+                    psyker.deny = value
+                        .parse()
+                        .map_err(|_| "Psyker deny is not a number: ".to_string() + value)?
+                }
+                "Powers Known" => {
+                    psyker.powers_known = value.to_string();
+                    psyker.discipline = DISCIPLINE_RE
+                        .captures(value)
+                        .map(|captures| captures[1].trim().to_string());
+                }
+                "Other" => psyker.other = value.to_string(),
+                _ => {
+                    return Err("Unknown characteristic for psyker: ".to_string()
+                        + characteristic.name.as_str())
+                }
+            }
+        }
+
+        Ok(psyker)
+    }
+}
+
+impl PsychicPowerType {
+    /// Gets the power type from the end of a selection name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.ends_with("(Witchfire)") {
+            Some(PsychicPowerType::Witchfire)
+        } else if name.ends_with("(Blessing)") {
+            Some(PsychicPowerType::Blessing)
+        } else if name.ends_with("(Malediction)") {
+            Some(PsychicPowerType::Malediction)
+        } else {
+            None
+        }
+    }
+}
+
+impl PsychicPower {
+    pub fn from_profile(
+        profile: &Profile,
+        power_type: Option<PsychicPowerType>,
+    ) -> Result<Self, String> {
+        lazy_static! {
+            // Regex to remove the discipline number from the name (i.e. "1. Gaze of Ynnead")
+            static ref NUMBER_RE: Regex = Regex::new(r"^\d+\. ").unwrap();
+            // Regex to parse distance values
+            static ref DIST_RE: Regex = Regex::new(r#"(\d+)""#).unwrap();
+        }
+
+        let mut power = Self {
+            name: NUMBER_RE.replace(&profile.name, "").to_string(),
+            power_type,
+            warp_charge: 0,
+            range: None,
+            details: "".to_string(),
+        };
+
+        for characteristic in &profile.characteristics.characteristics {
+            let value = characteristic.value.as_deref().unwrap_or("");
+            match characteristic.name.as_str() {
+                "Warp Charge" => {
+                    power.warp_charge = value
+                        .parse()
+                        .map_err(|_| "Warp charge is not a number: ".to_string() + value)?
+                }
+                // Range can be a distance or something like "Self"
+                "Range" => {
+                    power.range = DIST_RE
+                        .captures(value)
+                        .map(|captures| captures[1].parse().unwrap())
+                }
+                "Details" => power.details = value.to_string(),
+                _ => {
+                    return Err("Unknown characteristic for psychic power: ".to_string()
+                        + characteristic.name.as_str())
+                }
+            }
+        }
+
+        Ok(power)
+    }
+}
+
+/// Gets the psyker profile from the selection or any of its selections.
+pub fn parse_psyker(selection: &Selection) -> Result<Option<Psyker>, String> {
+    find_profile(selection, "Psyker")
+        .map(Psyker::from_profile)
+        .transpose()
+}
+
+/// Gets the psychic powers from the selection and all of its selections.
+pub fn parse_psychic_powers(selection: &Selection) -> Result<Vec<PsychicPower>, String> {
+    let mut v: Vec<PsychicPower> = Vec::new();
+
+    if let Some(profiles) = &selection.profiles {
+        for profile in &profiles.profiles {
+            if profile.type_name == "Psychic Power" {
+                v.push(PsychicPower::from_profile(
+                    profile,
+                    PsychicPowerType::from_name(&selection.name),
+                )?);
+            }
+        }
+    }
+
+    if let Some(selections) = &selection.selections {
+        for selection in &selections.selections {
+            v.append(&mut parse_psychic_powers(selection)?);
+        }
+    }
+
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::load_unit;
+
+    #[test]
+    fn psyker_and_powers_from_roster() {
+        let yncarne = load_unit("Test_Roster.ros", "The Yncarne");
+
+        let psyker = yncarne.psyker.unwrap();
+        assert_eq!((psyker.cast, psyker.deny), (2, 2));
+        assert_eq!(psyker.discipline.as_deref(), Some("Revenant"));
+
+        let names: Vec<&str> = yncarne
+            .psychic_powers
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Smite",
+                "Gaze of Ynnead",
+                "Storm of Whispers",
+                "Word of the Phoenix",
+                "Unbind Souls",
+                "Shield of Ynnead",
+                "Ancestors' Grace"
+            ]
+        );
+
+        let smite = &yncarne.psychic_powers[0];
+        assert_eq!(smite.power_type, None);
+        assert_eq!((smite.warp_charge, smite.range), (5, Some(18)));
+
+        let gaze = &yncarne.psychic_powers[1];
+        assert_eq!(gaze.power_type, Some(PsychicPowerType::Witchfire));
+        assert_eq!((gaze.warp_charge, gaze.range), (6, Some(18)));
+    }
+
+    #[test]
+    fn power_type_from_selection_name() {
+        let cases = [
+            (
+                "1. Gaze of Ynnead (Witchfire)",
+                Some(PsychicPowerType::Witchfire),
+            ),
+            (
+                "5. Shield of Ynnead (Blessing)",
+                Some(PsychicPowerType::Blessing),
+            ),
+            (
+                "4. Unbind Souls (Malediction)",
+                Some(PsychicPowerType::Malediction),
+            ),
+            ("Smite", None),
+        ];
+
+        for (name, power_type) in cases {
+            assert_eq!(PsychicPowerType::from_name(name), power_type, "{}", name);
+        }
+    }
+}
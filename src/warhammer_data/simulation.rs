@@ -0,0 +1,382 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::combat::{hit_roll_needed, save_roll_needed, wound_roll_needed};
+use super::defence::{DefensiveTraits, InvulnerableSave};
+use super::edition::Edition;
+use super::profile::ProfileValue;
+use super::unit::{Model, ModelProfile, Unit};
+use super::weapon::{
+    choose_ranged_weapons, MovementState, Weapon, WeaponProfile, WeaponStrength,
+    WeaponStrengthType, WeaponType,
+};
+
+/// Phase that the attacking unit makes its attacks in.
+#[derive(Debug, Clone, Copy)]
+pub enum AttackPhase {
+    Shooting {
+        movement: MovementState,
+        target_distance: f32,
+    },
+    Fight,
+}
+
+/// Distributions from a simulation. Each vector is indexed by the result and holds the number of
+/// iterations that had that result.
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub iterations: u32,
+    pub models_killed: Vec<u32>,
+    pub damage_dealt: Vec<u32>,
+}
+
+/// A single attack to be resolved against the defending unit.
+struct Attack<'a> {
+    weapon: &'a Weapon,
+    skill: u8,
+    hit_modifier: i8,
+    user_strength: u8,
+}
+
+/// A defending model and the wounds it has remaining.
+struct Defender<'a> {
+    profile: &'a ModelProfile,
+    invulnerable_save: Option<u8>,
+    defensive_traits: &'a DefensiveTraits,
+    wounds: u8,
+}
+
+impl SimulationResult {
+    fn mean(distribution: &[u32], iterations: u32) -> f32 {
+        let total: u32 = distribution
+            .iter()
+            .enumerate()
+            .map(|(value, count)| value as u32 * count)
+            .sum();
+
+        total as f32 / iterations as f32
+    }
+
+    pub fn mean_models_killed(&self) -> f32 {
+        Self::mean(&self.models_killed, self.iterations)
+    }
+
+    pub fn mean_damage_dealt(&self) -> f32 {
+        Self::mean(&self.damage_dealt, self.iterations)
+    }
+
+    /// Chance of killing at least the given number of models.
+    pub fn chance_to_kill(&self, models: usize) -> f32 {
+        let count: u32 = self.models_killed.iter().skip(models).sum();
+
+        count as f32 / self.iterations as f32
+    }
+}
+
+/// Models without a melee weapon fight with a close combat weapon.
+fn close_combat_weapon() -> Weapon {
+    Weapon {
+        name: "Close combat weapon".to_string(),
+        profile: WeaponProfile {
+            abilities: None,
+            range: None,
+            weapon_type: WeaponType::Melee,
+            attacks: None,
+            strength: WeaponStrength {
+                value: 0,
+                strength_type: WeaponStrengthType::Addition,
+            },
+            armour_penetration: 0,
+            damage: ProfileValue {
+                dice_value: None,
+                flat_value: Some(1),
+            },
+        },
+        number: 1,
+    }
+}
+
+/// Rolls a D6 and checks it against the needed value. An unmodified 1 always fails.
+fn roll_success<R: Rng>(rng: &mut R, needed: u8) -> bool {
+    let roll: u8 = rng.gen_range(1..=6);
+    roll != 1 && roll >= needed
+}
+
+/// Rolls the number of attacks each weapon of the attacking models makes.
+/// Only one model in the unit can throw a grenade.
+fn roll_attacks<'a, R: Rng>(
+    rng: &mut R,
+    attacker_models: &'a [Model],
+    phase: AttackPhase,
+    close_combat_weapon: &'a Weapon,
+) -> Vec<Attack<'a>> {
+    let mut attacks: Vec<Attack> = Vec::new();
+    let mut grenade_thrown = false;
+
+    for model in attacker_models {
+        // Attacking models are undamaged
+        let profile = match model.profile_for_wounds(model.wounds()) {
+            Some(profile) => profile,
+            None => continue,
+        };
+
+        match phase {
+            AttackPhase::Shooting {
+                movement,
+                target_distance,
+            } => {
+                let fired = choose_ranged_weapons(
+                    &model.weapons,
+                    movement,
+                    target_distance,
+                    !grenade_thrown,
+                );
+                for (weapon, shot_profile) in fired {
+                    grenade_thrown |= weapon.profile.weapon_type == WeaponType::Grenade;
+
+                    for _ in 0..shot_profile.shots.roll(rng) {
+                        attacks.push(Attack {
+                            weapon,
+                            skill: profile.ballistic_skill,
+                            hit_modifier: shot_profile.hit_modifier,
+                            user_strength: profile.strength,
+                        });
+                    }
+                }
+            }
+            AttackPhase::Fight => {
+                // Each model fights with its first melee weapon
+                let weapon = model
+                    .weapons
+                    .iter()
+                    .find(|weapon| matches!(weapon.profile.weapon_type, WeaponType::Melee))
+                    .unwrap_or(close_combat_weapon);
+
+                for _ in 0..model.number {
+                    for _ in 0..profile.attacks.roll(rng) {
+                        attacks.push(Attack {
+                            weapon,
+                            skill: profile.weapon_skill,
+                            hit_modifier: 0,
+                            user_strength: profile.strength,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    attacks
+}
+
+/// Runs the attack sequence once and returns the models killed and damage dealt.
+fn simulate_once<R: Rng>(
+    rng: &mut R,
+    attacker_models: &[Model],
+    defender: &Unit,
+    phase: AttackPhase,
+    edition: Edition,
+    close_combat_weapon: &Weapon,
+) -> (usize, usize) {
+    let ranged = matches!(phase, AttackPhase::Shooting { .. });
+
+    // Attacks are allocated to the first model until it is destroyed
+    let mut defenders: Vec<Defender> = Vec::new();
+    for model in &defender.models {
+        // Use the unit's saves and traits for models that do not have their own
+        let invulnerable_save = if model.invulnerable_saves.is_empty() {
+            InvulnerableSave::best(&defender.invulnerable_saves, ranged)
+        } else {
+            InvulnerableSave::best(&model.invulnerable_saves, ranged)
+        };
+        let defensive_traits = if model.defensive_traits.is_empty() {
+            &defender.defensive_traits
+        } else {
+            &model.defensive_traits
+        };
+
+        if let Some(profile) = model.profile_for_wounds(model.wounds()) {
+            for _ in 0..model.number {
+                defenders.push(Defender {
+                    profile,
+                    invulnerable_save,
+                    defensive_traits,
+                    wounds: model.wounds(),
+                });
+            }
+        }
+    }
+
+    let mut models_killed: usize = 0;
+    let mut damage_dealt: usize = 0;
+
+    for attack in roll_attacks(rng, attacker_models, phase, close_combat_weapon) {
+        let target = match defenders.get_mut(models_killed) {
+            Some(target) => target,
+            None => break,
+        };
+
+        // Hit roll, a skill of 0 can not hit
+        if !attack.weapon.profile.automatically_hits()
+            && (attack.skill == 0
+                || !roll_success(
+                    rng,
+                    hit_roll_needed(attack.skill, attack.hit_modifier, edition),
+                ))
+        {
+            continue;
+        }
+
+        // Wound roll
+        let wound = wound_roll_needed(
+            &attack.weapon.profile.strength,
+            attack.user_strength,
+            target.profile.toughness,
+            0,
+            edition,
+        );
+        if !roll_success(rng, wound) {
+            continue;
+        }
+
+        // Saving throw
+        let save = save_roll_needed(
+            target.profile.save,
+            attack.weapon.profile.armour_penetration,
+            target.invulnerable_save,
+        );
+        if roll_success(rng, save) {
+            continue;
+        }
+
+        // Each point of damage can be ignored by a feel no pain roll
+        let mut damage = target
+            .defensive_traits
+            .modify_damage(attack.weapon.profile.damage.roll(rng));
+        if let Some(feel_no_pain) = target.defensive_traits.best_feel_no_pain(false) {
+            damage = (0..damage)
+                .filter(|_| !roll_success(rng, feel_no_pain))
+                .count() as u8;
+        }
+
+        // Inflict damage, excess damage is lost when the model is destroyed
+        let damage = damage.min(target.wounds);
+        target.wounds -= damage;
+        damage_dealt += damage as usize;
+        if target.wounds == 0 {
+            models_killed += 1;
+        }
+    }
+
+    (models_killed, damage_dealt)
+}
+
+/// Simulates the attacker attacking the defender the given number of times. Using the same seed
+/// gives the same results. Weapon types follow the 9th edition rules, so 10th edition is not supported.
+pub fn simulate(
+    attacker: &Unit,
+    defender: &Unit,
+    phase: AttackPhase,
+    edition: Edition,
+    iterations: u32,
+    seed: u64,
+) -> Result<SimulationResult, String> {
+    if edition != Edition::Ninth {
+        return Err("Simulations only support 9th edition weapon types".to_string());
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let close_combat_weapon = close_combat_weapon();
+    // Each model chooses the weapons it fires
+    let attacker_models = attacker.individual_models();
+
+    let mut result = SimulationResult {
+        iterations,
+        models_killed: Vec::new(),
+        damage_dealt: Vec::new(),
+    };
+
+    for _ in 0..iterations {
+        let (models_killed, damage_dealt) = simulate_once(
+            &mut rng,
+            &attacker_models,
+            defender,
+            phase,
+            edition,
+            &close_combat_weapon,
+        );
+
+        if result.models_killed.len() <= models_killed {
+            result.models_killed.resize(models_killed + 1, 0);
+        }
+        result.models_killed[models_killed] += 1;
+
+        if result.damage_dealt.len() <= damage_dealt {
+            result.damage_dealt.resize(damage_dealt + 1, 0);
+        }
+        result.damage_dealt[damage_dealt] += 1;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::army::Army;
+    use crate::warhammer_data::load_roster;
+
+    fn conscripts() -> Unit {
+        let mut army = Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap();
+        let units = &mut army.detachments[0].units;
+        let i = units.iter().position(|u| u.name == "Conscripts").unwrap();
+        units.remove(i)
+    }
+
+    #[test]
+    fn conscripts_throw_one_grenade_instead_of_shooting() {
+        let conscripts = conscripts();
+        let phase = AttackPhase::Shooting {
+            movement: MovementState::RemainedStationary,
+            target_distance: 6.0,
+        };
+
+        let result = simulate(&conscripts, &conscripts, phase, Edition::Ninth, 2000, 0).unwrap();
+
+        // 38 lasgun shots and one frag grenade kill 38 / 9 + 3.5 / 9 models
+        let expected = 41.5 / 9.0;
+        assert!(
+            (result.mean_models_killed() - expected).abs() < 0.3,
+            "{} models killed",
+            result.mean_models_killed()
+        );
+    }
+
+    #[test]
+    fn simulations_are_seeded() {
+        let conscripts = conscripts();
+        let phase = AttackPhase::Fight;
+
+        let first = simulate(&conscripts, &conscripts, phase, Edition::Ninth, 100, 7).unwrap();
+        let second = simulate(&conscripts, &conscripts, phase, Edition::Ninth, 100, 7).unwrap();
+
+        assert_eq!(first.models_killed, second.models_killed);
+        assert_eq!(first.damage_dealt, second.damage_dealt);
+    }
+
+    #[test]
+    fn tenth_edition_is_rejected() {
+        let conscripts = conscripts();
+
+        let result = simulate(
+            &conscripts,
+            &conscripts,
+            AttackPhase::Fight,
+            Edition::Tenth,
+            1,
+            0,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use super::army::Army;
+use super::cost::CostMode;
+use super::unit::Unit;
+use super::weapon::WeaponType;
+
+/// Statistics derived from a unit's models and points or Power Level.
+#[derive(Debug, Clone)]
+pub struct UnitStats {
+    pub name: String,
+    pub models: u32,
+    pub wounds: u32,
+    pub cost_mode: CostMode,
+    pub cost: f32,
+    pub cost_per_model: f32,
+    pub cost_per_wound: f32,
+    /// Ranged weapons other than grenades.
+    pub ranged_weapons: u32,
+    pub melee_weapons: u32,
+    pub grenades: u32,
+    /// Average save of the models, where models without a save count as 7+.
+    pub average_save: f32,
+}
+
+/// Statistics of every unit in an army and their totals.
+#[derive(Debug, Clone)]
+pub struct ArmyStats {
+    pub units: Vec<UnitStats>,
+    pub models: u32,
+    pub wounds: u32,
+    pub cost_mode: CostMode,
+    pub cost: f32,
+    pub cost_per_model: f32,
+    pub cost_per_wound: f32,
+    pub ranged_weapons: u32,
+    pub melee_weapons: u32,
+    pub grenades: u32,
+    pub average_save: f32,
+}
+
+/// Divides without dividing by 0.
+fn ratio(value: f32, by: u32) -> f32 {
+    match by {
+        0 => 0.0,
+        _ => value / by as f32,
+    }
+}
+
+impl UnitStats {
+    pub fn from_unit(unit: &Unit, mode: CostMode) -> Self {
+        let mut models: u32 = 0;
+        let mut wounds: u32 = 0;
+        let mut total_save: u32 = 0;
+        let mut ranged_weapons: u32 = 0;
+        let mut melee_weapons: u32 = 0;
+        let mut grenades: u32 = 0;
+
+        for model in &unit.models {
+            let number = model.number as u32;
+            models += number;
+            wounds += model.wounds() as u32 * number;
+
+            let save = match model.profile_for_wounds(model.wounds()) {
+                Some(profile) if profile.save != 0 => profile.save,
+                _ => 7,
+            };
+            total_save += save as u32 * number;
+
+            for weapon in &model.weapons {
+                match weapon.profile.weapon_type {
+                    WeaponType::Melee => melee_weapons += weapon.number as u32,
+                    WeaponType::Grenade => grenades += weapon.number as u32,
+                    _ => ranged_weapons += weapon.number as u32,
+                }
+            }
+        }
+
+        Self {
+            name: unit.name.to_owned(),
+            models,
+            wounds,
+            cost_mode: mode,
+            cost: unit.cost(mode),
+            cost_per_model: ratio(unit.cost(mode), models),
+            cost_per_wound: ratio(unit.cost(mode), wounds),
+            ranged_weapons,
+            melee_weapons,
+            grenades,
+            average_save: ratio(total_save as f32, models),
+        }
+    }
+}
+
+impl ArmyStats {
+    pub fn from_army(army: &Army, mode: CostMode) -> Self {
+        let units: Vec<UnitStats> = army
+            .detachments
+            .iter()
+            .flat_map(|d| d.units.iter())
+            .map(|unit| UnitStats::from_unit(unit, mode))
+            .collect();
+
+        let models: u32 = units.iter().map(|u| u.models).sum();
+        let wounds: u32 = units.iter().map(|u| u.wounds).sum();
+        let cost: f32 = units.iter().map(|u| u.cost).sum();
+        // Saves are weighted by the number of models in each unit
+        let total_save: f32 = units.iter().map(|u| u.average_save * u.models as f32).sum();
+
+        Self {
+            models,
+            wounds,
+            cost_mode: mode,
+            cost,
+            cost_per_model: ratio(cost, models),
+            cost_per_wound: ratio(cost, wounds),
+            ranged_weapons: units.iter().map(|u| u.ranged_weapons).sum(),
+            melee_weapons: units.iter().map(|u| u.melee_weapons).sum(),
+            grenades: units.iter().map(|u| u.grenades).sum(),
+            average_save: ratio(total_save, models),
+            units,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::{load_roster, load_unit};
+
+    #[test]
+    fn grenades_are_not_ranged_weapons() {
+        let conscripts = load_unit("Astra_1000/Astra_1000.ros", "Conscripts");
+        let stats = UnitStats::from_unit(&conscripts, CostMode::Points);
+
+        assert_eq!(stats.models, 20);
+        assert_eq!(stats.ranged_weapons, 20);
+        assert_eq!(stats.grenades, 20);
+        assert_eq!(stats.melee_weapons, 0);
+    }
+
+    #[test]
+    fn army_totals_of_the_sample_roster() {
+        let army = Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap();
+        let stats = ArmyStats::from_army(&army, CostMode::Points);
+
+        assert_eq!(stats.units.len(), 9);
+        assert_eq!(stats.models, 72);
+        assert_eq!(stats.cost, 1000.0);
+        assert_eq!(stats.ranged_weapons, 84);
+        assert_eq!(stats.grenades, 67);
+        assert_eq!(stats.melee_weapons, 2);
+    }
+}
@@ -0,0 +1,351 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::profile::find_profile;
+use super::ros_parser::ros_parser::{Profile, Selection};
+use super::unit::Unit;
+
+/// Transport capacity and restrictions from a "Transport" profile.
+#[derive(Debug, Clone)]
+pub struct Transport {
+    /// Transports with a description that could not be read have no capacity.
+    pub capacity: Option<u8>,
+    /// Keywords that embarked models must have, any one of these can be matched
+    /// (i.e. "MILITARUM TEMPESTUS INFANTRY" or "<REGIMENT> INFANTRY").
+    pub allowed_keywords: Vec<String>,
+    /// Keywords of models that can not embark.
+    pub excluded_keywords: Vec<String>,
+    /// Keywords of models that take the space of more than one model and how many spaces they take.
+    pub bulky_keywords: Vec<(String, u8)>,
+    pub description: String,
+}
+
+/// Result of checking if units can embark within a transport.
+#[derive(Debug, Clone)]
+pub struct EmbarkCheck {
+    pub capacity: Option<u8>,
+    pub space_used: u32,
+    pub problems: Vec<String>,
+}
+
+impl EmbarkCheck {
+    pub fn fits(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Puts a keyword in the form it is compared in, upper case with each word singular, as rules text
+/// and rosters do not agree on plurals (i.e. "HEAVY WEAPONS TEAM" and "Heavy Weapon Team").
+fn normalise_keyword(keyword: &str) -> String {
+    keyword
+        .to_uppercase()
+        .split_whitespace()
+        .map(|word| match word.strip_suffix('S') {
+            Some(singular) if singular.len() > 2 && !singular.ends_with('S') => singular,
+            _ => word,
+        })
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Replaces placeholder keywords (i.e. "<REGIMENT>") with the detachment's subfaction.
+fn resolve_placeholder(keyword: &str, subfaction: Option<&str>) -> String {
+    lazy_static! {
+        static ref PLACEHOLDER_RE: Regex = Regex::new(r"<[^>]+>").unwrap();
+    }
+
+    match subfaction {
+        Some(subfaction) => PLACEHOLDER_RE.replace_all(keyword, subfaction).to_string(),
+        None => keyword.to_owned(),
+    }
+}
+
+/// Checks if a keyword phrase from rules text (i.e. "DRUKHARI INFANTRY") is made up of the keywords.
+fn matches_keywords(phrase: &str, keywords: &[String]) -> bool {
+    let keywords: Vec<String> = keywords.iter().map(|k| normalise_keyword(k)).collect();
+
+    // Take the longest keyword from the start of the phrase until nothing is left
+    let mut rest = normalise_keyword(phrase);
+    while !rest.is_empty() {
+        let longest = keywords
+            .iter()
+            .filter(|k| rest == **k || rest.starts_with(&(k.to_string() + " ")))
+            .max_by_key(|k| k.len());
+
+        match longest {
+            Some(keyword) => rest = rest[keyword.len()..].trim_start().to_string(),
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// Splits a list of keywords from rules text (i.e. "GROTESQUES, SCOURGES or SKYBOARD").
+fn split_keywords(list: &str) -> Vec<String> {
+    list.split(", ")
+        .flat_map(|s| s.split(" or "))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+impl Transport {
+    pub fn from_profile(profile: &Profile) -> Self {
+        let description = match profile.characteristics.characteristics.first() {
+            Some(characteristic) => characteristic.value.to_owned().unwrap_or_default(),
+            None => String::new(),
+        };
+
+        Self::from_description(&description)
+    }
+
+    /// Reads the capacity and keywords from the transport's rules text. Anything that can not be
+    /// read is left empty and the description is kept.
+    pub fn from_description(description: &str) -> Self {
+        lazy_static! {
+            // Regex to get the capacity and the keywords of models that can embark
+            static ref CAPACITY_RE: Regex =
+                Regex::new(r"(?i)(?:transport capacity of|can transport) (\d+) (.*?) ?models?\b").unwrap();
+            // Regex to get the models that can not embark
+            static ref EXCLUDED_RE: Regex =
+                Regex::new(r"(?i)(?:cannot|can not|can't) transport (.+?) models?\b").unwrap();
+            // Regex to get models that take more space (i.e. "Each JUMP PACK model takes the space of two other models")
+            static ref BULKY_RE: Regex = Regex::new(
+                r"(?i)each (.+?) (?:model )?takes (?:up )?the space of (\d+|two|three|four|five|six) (?:other )?models"
+            )
+            .unwrap();
+        }
+
+        let captures = CAPACITY_RE.captures(description);
+
+        let bulky_keywords = BULKY_RE
+            .captures_iter(description)
+            .map(|captures| {
+                let spaces = match captures[2].to_lowercase().as_str() {
+                    "two" => 2,
+                    "three" => 3,
+                    "four" => 4,
+                    "five" => 5,
+                    "six" => 6,
+                    number => number.parse().unwrap(),
+                };
+                (captures[1].to_string(), spaces)
+            })
+            .collect();
+
+        Self {
+            capacity: captures.as_ref().and_then(|c| c[1].parse().ok()),
+            allowed_keywords: match &captures {
+                Some(captures) => split_keywords(&captures[2]),
+                None => Vec::new(),
+            },
+            excluded_keywords: match EXCLUDED_RE.captures(description) {
+                Some(excluded) => split_keywords(&excluded[1]),
+                None => Vec::new(),
+            },
+            bulky_keywords,
+            description: description.to_owned(),
+        }
+    }
+
+    /// Checks if the units can embark within the transport together. Placeholder keywords of the
+    /// transport and the units (i.e. "<REGIMENT> INFANTRY") are the detachment's subfaction.
+    pub fn check_embark(&self, units: &[&Unit], subfaction: Option<&str>) -> EmbarkCheck {
+        let mut check = EmbarkCheck {
+            capacity: self.capacity,
+            space_used: 0,
+            problems: Vec::new(),
+        };
+        let resolve = |phrase: &String| resolve_placeholder(phrase, subfaction);
+
+        for unit in units {
+            let unit_keywords: Vec<String> = unit.keywords.all().iter().map(resolve).collect();
+
+            if !self.allowed_keywords.is_empty()
+                && !self
+                    .allowed_keywords
+                    .iter()
+                    .any(|phrase| matches_keywords(&resolve(phrase), &unit_keywords))
+            {
+                check.problems.push(format!(
+                    "{} does not have the keywords {}",
+                    unit.name,
+                    self.allowed_keywords.join(" or ")
+                ));
+            }
+
+            for phrase in &self.excluded_keywords {
+                if matches_keywords(&resolve(phrase), &unit_keywords) {
+                    check.problems.push(format!(
+                        "{} can not embark because it is {}",
+                        unit.name, phrase
+                    ));
+                }
+            }
+
+            for model in &unit.models {
+                // Rosters do not always have the model's keywords (i.e. HEAVY WEAPONS TEAM is only
+                // the model's name)
+                let mut keywords = unit_keywords.clone();
+                keywords.extend(model.keywords.iter().map(resolve));
+                keywords.push(model.name.to_owned());
+
+                let spaces = self
+                    .bulky_keywords
+                    .iter()
+                    .filter(|(phrase, _)| matches_keywords(&resolve(phrase), &keywords))
+                    .map(|(_, spaces)| *spaces)
+                    .max()
+                    .unwrap_or(1);
+
+                check.space_used += model.number as u32 * spaces as u32;
+            }
+        }
+
+        match self.capacity {
+            Some(capacity) if check.space_used > capacity as u32 => check.problems.push(format!(
+                "{} models worth of space is over the capacity of {}",
+                check.space_used, capacity
+            )),
+            Some(_) => (),
+            None => check
+                .problems
+                .push("Unknown transport capacity: ".to_string() + &self.description),
+        }
+
+        check
+    }
+}
+
+/// Gets the transport profile from the selection or any of its selections.
+pub fn parse_transport(selection: &Selection) -> Option<Transport> {
+    find_profile(selection, "Transport").map(Transport::from_profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::army::Army;
+    use crate::warhammer_data::load_roster;
+
+    #[test]
+    fn capacity_and_keywords_are_read() {
+        let transport = Transport::from_description(
+            "This model has a transport capacity of 11 <REGIMENT> INFANTRY models. Each HEAVY WEAPONS TEAM takes the space of two other models. It cannot transport JUMP PACK models.",
+        );
+
+        assert_eq!(transport.capacity, Some(11));
+        assert_eq!(transport.allowed_keywords, ["<REGIMENT> INFANTRY"]);
+        assert_eq!(transport.excluded_keywords, ["JUMP PACK"]);
+        assert_eq!(
+            transport.bulky_keywords,
+            [("HEAVY WEAPONS TEAM".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn single_model_capacity_is_read() {
+        let transport = Transport::from_description("This model can transport 1 CHARACTER model.");
+
+        assert_eq!(transport.capacity, Some(1));
+        assert_eq!(transport.allowed_keywords, ["CHARACTER"]);
+    }
+
+    #[test]
+    fn unknown_capacity_keeps_the_description() {
+        let description = "This model can carry a squad into battle.";
+        let transport = Transport::from_description(description);
+
+        assert_eq!(transport.capacity, None);
+        assert!(transport.allowed_keywords.is_empty());
+        assert_eq!(transport.description, description);
+        assert!(!transport.check_embark(&[], None).fits());
+    }
+
+    /// Chimera transport rules from the 9th edition codex.
+    fn chimera() -> Transport {
+        Transport::from_description(
+            "This model has a transport capacity of 12 <REGIMENT> INFANTRY models. Each HEAVY WEAPONS TEAM takes the space of two other models. It cannot transport JUMP PACK models.",
+        )
+    }
+
+    fn units(names: &[&str]) -> Vec<Unit> {
+        let army = Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap();
+        let mut units: Vec<Unit> = army.detachments.into_iter().flat_map(|d| d.units).collect();
+        units.retain(|u| names.contains(&u.name.as_str()));
+        units.dedup_by(|a, b| a.name == b.name);
+        units
+    }
+
+    #[test]
+    fn keywords_are_compared_singular() {
+        let keywords = ["Heavy Weapon Team".to_string()];
+
+        assert!(matches_keywords("HEAVY WEAPONS TEAM", &keywords));
+        assert!(matches_keywords("HEAVY WEAPONS TEAMS", &keywords));
+        assert!(!matches_keywords("HEAVY WEAPONS SQUAD", &keywords));
+    }
+
+    #[test]
+    fn heavy_weapon_teams_take_two_spaces() {
+        let squad = units(&["Heavy Weapons Squad"]);
+
+        let check = chimera().check_embark(&[&squad[0]], Some("Cadian"));
+
+        assert_eq!(check.space_used, 6);
+        assert!(check.fits(), "{:?}", check.problems);
+    }
+
+    #[test]
+    fn regiment_is_the_detachment_subfaction() {
+        let mut units = units(&["Lord Castellan Creed", "Command Squad", "Lord Commissar"]);
+        let creed = units
+            .iter_mut()
+            .find(|u| u.name == "Lord Castellan Creed")
+            .unwrap();
+        // The roster leaves out Creed's INFANTRY keyword
+        creed.keywords.unit_type.push("Infantry".to_string());
+        let creed = units
+            .iter()
+            .find(|u| u.name == "Lord Castellan Creed")
+            .unwrap();
+        let command_squad = units.iter().find(|u| u.name == "Command Squad").unwrap();
+        let commissar = units.iter().find(|u| u.name == "Lord Commissar").unwrap();
+
+        // Creed is Cadian and the Command Squad is from the detachment's regiment
+        let check = chimera().check_embark(&[creed, command_squad], Some("Cadian"));
+        assert!(check.fits(), "{:?}", check.problems);
+        assert_eq!(check.space_used, 5);
+
+        // Creed is not from another regiment
+        assert!(!chimera().check_embark(&[creed], Some("Catachan")).fits());
+
+        // Commissars are not part of a regiment
+        let check = chimera().check_embark(&[commissar], Some("Cadian"));
+        assert_eq!(
+            check.problems,
+            ["Lord Commissar does not have the keywords <REGIMENT> INFANTRY"]
+        );
+    }
+
+    #[test]
+    fn over_capacity_and_wrong_unit_types_are_problems() {
+        let units = units(&["Conscripts", "Leman Russ Battle Tanks"]);
+        let conscripts = units.iter().find(|u| u.name == "Conscripts").unwrap();
+        let leman_russ = units
+            .iter()
+            .find(|u| u.name == "Leman Russ Battle Tanks")
+            .unwrap();
+
+        let check = chimera().check_embark(&[conscripts], Some("Cadian"));
+        assert_eq!(check.space_used, 20);
+        assert_eq!(
+            check.problems,
+            ["20 models worth of space is over the capacity of 12"]
+        );
+
+        let check = chimera().check_embark(&[leman_russ], Some("Cadian"));
+        assert!(!check.fits());
+    }
+}
@@ -2,9 +2,25 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use super::ability::{parse_abilities, Ability};
+use super::cost::{get_cost, get_total_cost, CostMode};
+use super::defence::{DefensiveTraits, InvulnerableSave, SaveRestriction};
+use super::enhancement::{
+    is_relic, is_warlord_trait, parse_relic, parse_warlord, parse_warlord_trait, Enhancement,
+};
+use super::explosion::Explosion;
+use super::keyword::{BattlefieldRole, Keywords};
 use super::profile::ProfileValue;
+use super::psychic::{parse_psychic_powers, parse_psyker, PsychicPower, Psyker};
 use super::ros_parser::ros_parser::{Profile, Selection};
-use super::weapon::{Weapon};
+use super::transport::{parse_transport, Transport};
+use super::weapon::{LoadoutChange, Weapon};
+
+/// Range of wounds remaining that a damaged profile is used for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WoundBand {
+    pub min: u8,
+    pub max: u8,
+}
 
 /// Profile information from a unit's datasheet.
 #[derive(Debug, Clone)]
@@ -19,6 +35,16 @@ pub struct ModelProfile {
     pub attacks: ProfileValue,
     pub leadership: u8,
     pub save: u8,
+    /// Wounds remaining this profile is used for if the model has damage brackets.
+    pub wound_band: Option<WoundBand>,
+}
+
+/// Wargear without a weapon profile (i.e. Vox-caster or Dozer blade).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wargear {
+    pub name: String,
+    pub number: u8,
+    pub abilities: Vec<Ability>,
 }
 
 /// Model information from a unit's datasheet. Includes model profiles.
@@ -27,20 +53,37 @@ pub struct Model {
     pub name: String,
     pub profiles: Vec<ModelProfile>,
     pub weapons: Vec<Weapon>,
+    pub wargear: Vec<Wargear>,
     pub number: u8,
     pub keywords: Vec<String>,
+    pub invulnerable_saves: Vec<InvulnerableSave>,
+    pub defensive_traits: DefensiveTraits,
+    pub explosion: Option<Explosion>,
 }
 
 /// Unit information from a unit's datasheet. Includes models.
 #[derive(Debug)]
 pub struct Unit {
     pub name: String,
-    pub keywords: Vec<String>,
+    /// Id of the datasheet the unit was selected from.
+    pub entry_id: String,
+    /// Id of the unit's selection in the roster.
+    pub selection_id: String,
+    pub role: Option<BattlefieldRole>,
+    pub keywords: Keywords,
     pub abilities: Vec<Ability>,
-    pub invulnable_save: Option<u8>,
+    pub invulnerable_saves: Vec<InvulnerableSave>,
+    pub defensive_traits: DefensiveTraits,
     pub models: Vec<Model>,
     pub rules: Vec<String>,
     pub points: f32,
+    pub power_level: f32,
+    pub psyker: Option<Psyker>,
+    pub psychic_powers: Vec<PsychicPower>,
+    pub transport: Option<Transport>,
+    pub warlord: bool,
+    pub warlord_trait: Option<Enhancement>,
+    pub relic: Option<Enhancement>,
 }
 
 impl ModelProfile {
@@ -63,6 +106,7 @@ impl ModelProfile {
             },
             leadership: 0,
             save: 0,
+            wound_band: None,
         };
 
         lazy_static! {
@@ -73,7 +117,9 @@ impl ModelProfile {
             // Regex to parse range vaues
             static ref RANGE_RE: Regex = Regex::new(r"(\d+)-(\d+)").unwrap();
             // Regex to parse bracketed unit profiles since wounds show as N/A
-            static ref W_REM_RE: Regex = Regex::new(r"\[(\d+)\] \((\d+)[\+-](\d*) Wounds Remaining\)").unwrap();
+            // Some profiles are named "1. Name (7+ wounds remaining)" instead of "Name [1] (7+ Wounds Remaining)"
+            static ref W_REM_RE: Regex =
+                Regex::new(r"(?i)(?:\[(\d+)\] )?\((\d+)[\+-](\d*) Wounds Remaining\)").unwrap();
         }
 
         // Iterate through characteristics
@@ -201,7 +247,13 @@ impl ModelProfile {
                 "Remaining W" => {
                     model_profile.wounds =
                         match RANGE_RE.captures(characteristic.value.as_ref().unwrap()) {
-                            Some(m) => m.get(2).unwrap().as_str().parse().unwrap(),
+                            Some(m) => {
+                                model_profile.wound_band = Some(WoundBand {
+                                    min: m.get(1).unwrap().as_str().parse().unwrap(),
+                                    max: m.get(2).unwrap().as_str().parse().unwrap(),
+                                });
+                                m.get(2).unwrap().as_str().parse().unwrap()
+                            }
                             // None case is probably not possible unless Remaining W can be a single value
                             None => return Err("Remaining W is not a ranged value".to_owned()),
                         }
@@ -213,6 +265,17 @@ impl ModelProfile {
             }
         }
 
+        // Bracketed profiles have the wound band in the name, the top band uses the model's wounds
+        if let Some(captures) = W_REM_RE.captures(&profile.name) {
+            model_profile.wound_band = Some(WoundBand {
+                min: captures.get(2).unwrap().as_str().parse().unwrap(),
+                max: match captures.get(3).unwrap().as_str() {
+                    "" => model_profile.wounds,
+                    max => max.parse().unwrap(),
+                },
+            });
+        }
+
         Ok(model_profile)
     }
 }
@@ -222,10 +285,98 @@ impl Model {
         self.weapons.push(weapon.clone());
     }
 
+    /// Splits the model into one model for each of its number. Items that every model has are
+    /// shared out evenly, the other copies are given one per model in order, so the i-th copy
+    /// across all of the items goes to the i-th model (i.e. 2 heavy bolters and 2 lascannons
+    /// between 4 models give each model one of them).
+    pub fn split(&self) -> Vec<Model> {
+        let models = self.number as usize;
+        let mut split: Vec<Model> = (0..models)
+            .map(|_| Model {
+                number: 1,
+                weapons: Vec::new(),
+                wargear: Vec::new(),
+                ..self.clone()
+            })
+            .collect();
+
+        // Number of copies of an item each model gets, continuing from the last model given a copy
+        let share = |total: u8, next: &mut usize| -> Vec<u8> {
+            let total = total as usize;
+            let mut shares = vec![(total / models) as u8; models];
+            for _ in 0..total % models {
+                shares[*next] += 1;
+                *next = (*next + 1) % models;
+            }
+            shares
+        };
+
+        let mut next = 0;
+        for weapon in &self.weapons {
+            for (model, number) in split.iter_mut().zip(share(weapon.number, &mut next)) {
+                if number > 0 {
+                    model.weapons.push(Weapon {
+                        number,
+                        ..weapon.clone()
+                    });
+                }
+            }
+        }
+
+        let mut next = 0;
+        for wargear in &self.wargear {
+            for (model, number) in split.iter_mut().zip(share(wargear.number, &mut next)) {
+                if number > 0 {
+                    model.wargear.push(Wargear {
+                        number,
+                        ..wargear.clone()
+                    });
+                }
+            }
+        }
+
+        split
+    }
+
+    /// Starting wounds of the model, which is the highest wounds of its profiles.
+    pub fn wounds(&self) -> u8 {
+        self.profiles.iter().map(|p| p.wounds).max().unwrap_or(0)
+    }
+
+    /// Gets the profile to use for the wounds the model has remaining.
+    /// Models without damage brackets always use their first profile.
+    pub fn profile_for_wounds(&self, wounds_remaining: u8) -> Option<&ModelProfile> {
+        if wounds_remaining == 0 {
+            return None;
+        }
+
+        let bracketed: Vec<&ModelProfile> =
+            self.profiles.iter().filter(|p| p.wound_band.is_some()).collect();
+        if bracketed.is_empty() {
+            return self.profiles.first();
+        }
+
+        for profile in &bracketed {
+            let band = profile.wound_band.unwrap();
+            if band.min <= wounds_remaining && wounds_remaining <= band.max {
+                return Some(profile);
+            }
+        }
+
+        // Wounds above the top band (i.e. "7-12+") use the top band
+        bracketed.into_iter().max_by_key(|p| p.wound_band.unwrap().max)
+    }
+
     pub fn from_selection(model_selection: &Selection) -> Result<Model, String> {
         let mut weapons: Vec<Weapon> = Vec::new();
+        let mut wargear: Vec<Wargear> = Vec::new();
         let mut model_profiles: Vec<ModelProfile> = Vec::new();
         let mut keywords: Vec<String> = Vec::new();
+        // Selections of the model's weapons and upgrades (i.e. relics) that can change them
+        let mut weapon_selections: Vec<&Selection> = Vec::new();
+        let mut upgrade_selections: Vec<&Selection> = Vec::new();
+        let mut explosion: Option<Explosion> = None;
+        let warlord = parse_warlord(model_selection);
 
         match &model_selection.profiles {
             Some(profiles) => {
@@ -236,7 +387,9 @@ impl Model {
                             model_profiles.push(ModelProfile::from_profile(&profile).unwrap())
                         }
                         "Abilities" => (),
-                        "Explosion" => (), //TODO: whatever to do with this
+                        // Psychic and transport profiles are added to the unit
+                        "Psyker" | "Psychic Power" | "Transport" => (),
+                        "Explosion" => explosion = Some(Explosion::from_profile(profile)?),
                         _ => {
                             return Err("Unknown type name for profile: ".to_owned()
                                 + profile.type_name.as_str())
@@ -248,13 +401,14 @@ impl Model {
             None => return Err("Model does not have a profile".to_owned()),
         };
 
-        for selection in &model_selection.selections.as_ref().unwrap().selections {
+        // Some models (i.e. Wraithblades) do not have any selections
+        for selection in model_selection.selections.iter().flat_map(|s| s.selections.iter()) {
             // This covers cases where the weapons are in another selection.
             if !selection.selections.is_none() {
                 for s in &selection.selections.as_ref().unwrap().selections {
                     match s.profiles.as_ref().unwrap().profiles.get(0) {
                         Some(profile) => match profile.type_name.as_str() {
-                            "Abilities" => (),
+                            "Abilities" | "Psychic Power" => (),
                             "Weapon" => weapons.push(Weapon::from_selection(&s).unwrap()),
                             _ => {
                                 return Err(
@@ -284,9 +438,29 @@ impl Model {
             match selection.profiles.as_ref().unwrap().profiles.get(0) {
                 Some(profile) => {
                     match profile.type_name.as_str() {
-                        "Abilities" => (),
+                        // Relics and upgrades have their rules before the weapon profile
+                        "Abilities" => {
+                            let profiles = &selection.profiles.as_ref().unwrap().profiles;
+                            if profiles.iter().any(|p| p.type_name == "Weapon") {
+                                upgrade_selections.push(selection);
+                            } else if is_wargear(selection, model_selection)
+                                && !is_warlord_trait(selection, warlord)
+                                && !is_relic(selection)
+                            {
+                                wargear.push(Wargear {
+                                    name: selection.name.to_owned(),
+                                    number: selection.number,
+                                    abilities: parse_abilities(selection),
+                                });
+                            }
+                        }
+                        // Psychic powers are added to the unit
+                        "Psychic Power" => (),
                         // Push weapon
-                        "Weapon" => weapons.push(Weapon::from_selection(&selection).unwrap()),
+                        "Weapon" => {
+                            weapons.push(Weapon::from_selection(&selection).unwrap());
+                            weapon_selections.push(selection);
+                        }
                         // There are probably more of this type
                         "Stat Damage - M/BS/A" => {
                             let orig_profile = model_profiles.pop().unwrap();
@@ -316,6 +490,7 @@ impl Model {
                                     },
                                     leadership: orig_profile.leadership,
                                     save: orig_profile.save,
+                                    wound_band: tmp_profile.wound_band,
                                 })
                             }
                         }
@@ -326,6 +501,27 @@ impl Model {
             }
         }
 
+        // Add upgrade weapons, removing the weapons they replace
+        for upgrade_selection in upgrade_selections {
+            let change = LoadoutChange::from_selection(upgrade_selection, &weapon_selections);
+            if let LoadoutChange::Replace(name) = change {
+                if let Some(i) = weapons.iter().position(|w| w.name.eq_ignore_ascii_case(&name)) {
+                    weapons[i].number = weapons[i].number.saturating_sub(upgrade_selection.number);
+                    if weapons[i].number == 0 {
+                        weapons.remove(i);
+                    }
+                }
+            }
+
+            for profile in &upgrade_selection.profiles.as_ref().unwrap().profiles {
+                if profile.type_name == "Weapon" {
+                    let mut weapon = Weapon::from_profile(profile).unwrap();
+                    weapon.number = upgrade_selection.number;
+                    weapons.push(weapon);
+                }
+            }
+        }
+
         // Go through weapons and check if there are multiple enries of the same weapon
         let mut unmultiple_weapons: Vec<Weapon> = Vec::new();
         'outer: for i in 0..weapons.len() {
@@ -345,43 +541,81 @@ impl Model {
             unmultiple_weapons.push(temp);
         }
 
+        // Most vehicles only have the explosion in their abilities
+        let abilities = parse_abilities(model_selection);
+        if explosion.is_none() {
+            explosion = abilities.iter().find_map(Explosion::from_ability);
+        }
+
         // Create and return model
         Ok(Self {
             name: model_selection.name.to_owned(),
             profiles: model_profiles,
             weapons: unmultiple_weapons,
+            wargear,
             number: model_selection.number,
             keywords,
+            invulnerable_saves: InvulnerableSave::from_abilities(&abilities),
+            defensive_traits: DefensiveTraits::from_abilities(&abilities),
+            explosion,
         })
     }
 }
 
+/// Checks if an upgrade without a weapon profile is wargear and not one of the model's abilities
+/// (i.e. "Slow and Purposeful"). Wargear is bought with points or Power Level, is a free option in
+/// the same entry group as bought wargear, or comes with a model that was chosen as an option
+/// (i.e. the Medi-pack of a "Veteran w/ Medi-pack").
+fn is_wargear(selection: &Selection, model_selection: &Selection) -> bool {
+    let has_cost = |selection: &Selection| {
+        get_cost(&selection.costs, CostMode::Points.cost_name()) != 0.0
+            || get_cost(&selection.costs, CostMode::PowerLevel.cost_name()) != 0.0
+    };
+
+    if has_cost(selection) {
+        return true;
+    }
+
+    match &selection.entry_group_id {
+        Some(group) => model_selection
+            .selections
+            .iter()
+            .flat_map(|s| s.selections.iter())
+            .any(|s| s.entry_group_id.as_ref() == Some(group) && has_cost(s)),
+        None => model_selection.entry_group_id.is_some(),
+    }
+}
+
+/// Checks if the selection is a model by looking for its unit profile.
+fn has_model_profile(selection: &Selection) -> bool {
+    match &selection.profiles {
+        Some(profiles) => profiles.profiles.iter().any(|p| p.type_name == "Unit"),
+        None => false,
+    }
+}
+
 impl Unit {
-    fn get_total_points(unit_selection: &Selection) -> f32 {
-        let mut points: f32 = 0.0;
+    /// Best invulnerable save that can be used against every attack, in place of the old
+    /// `invulnable_save` field. Saves limited to ranged or melee attacks are in `invulnerable_saves`.
+    pub fn invulnable_save(&self) -> Option<u8> {
+        self.invulnerable_saves
+            .iter()
+            .filter(|save| save.restriction == SaveRestriction::All)
+            .map(|save| save.value)
+            .min()
+    }
 
-        match &unit_selection.selections {
-            Some(selections) => {
-                for selection in &selections.selections {
-                    points += Self::get_total_points(&selection);
-                }
-            },
-            None => (),
-        }
-        
+    /// Every model in the unit on its own with its own weapons and wargear.
+    pub fn individual_models(&self) -> Vec<Model> {
+        self.models.iter().flat_map(|model| model.split()).collect()
+    }
 
-        match &unit_selection.costs {
-            Some(costs) => {
-                for cost in &costs.costs {
-                    if cost.name == "pts" {
-                        points += cost.value;
-                    }
-                }
-            },
-            None => (),
+    /// Points or Power Level of the unit.
+    pub fn cost(&self, mode: CostMode) -> f32 {
+        match mode {
+            CostMode::Points => self.points,
+            CostMode::PowerLevel => self.power_level,
         }
-
-        points
     }
 
     pub fn from_selection(unit_selection: &Selection) -> Result<Self, String> {
@@ -400,6 +634,7 @@ impl Unit {
                         },
                         "Unit" => (),
                         "Abilities" => (),
+                        "Psyker" | "Psychic Power" | "Transport" => (),
                         _ => return Err("Unknown unit profile type: ".to_string() + &profile.type_name)
                     }
                 }
@@ -413,18 +648,32 @@ impl Unit {
             "model" => models.push(Model::from_selection(unit_selection).unwrap()),
             // Selection is the unit which has selections of models
             "unit" => {
-                for model in &unit_selection.selections.as_ref().unwrap().selections {
+                let selections = &unit_selection.selections.as_ref().unwrap().selections;
+
+                // Upgrades without a model profile (i.e. Ghostaxe and Forceshield) are taken by every model
+                for upgrade in selections.iter().filter(|s| !has_model_profile(s)) {
+                    for profile in upgrade.profiles.iter().flat_map(|p| p.profiles.iter()) {
+                        if profile.type_name == "Weapon" {
+                            unit_weapons.push(Weapon::from_profile(profile).unwrap());
+                        }
+                    }
+                }
+
+                for model in selections {
                     match model.profiles {
                         // Push model into unit
-                        Some(_) => {
+                        Some(_) if has_model_profile(model) => {
                             let mut m = Model::from_selection(&model).unwrap();
+                            // Weapons on the unit are carried by every model
                             for weapon in &unit_weapons {
-                                m.append_weapon(weapon);
+                                let mut weapon = weapon.clone();
+                                weapon.number = m.number;
+                                m.append_weapon(&weapon);
                             }
                             models.push(m);
 
                         },
-                        None => (),
+                        _ => (),
                     }
                     // Get more keywords from the selections
                     match &model.categories {
@@ -453,7 +702,8 @@ impl Unit {
             }
         };
 
-        for category in &unit_selection.categories.as_ref().unwrap().categories {
+        let categories = &unit_selection.categories.as_ref().unwrap().categories;
+        for category in categories {
             keywords.push(category.name.to_owned())
         }
 
@@ -463,14 +713,226 @@ impl Unit {
         rules.sort_unstable();
         rules.dedup();
 
+        let abilities = parse_abilities(unit_selection);
+
         Ok(Unit {
             name: unit_selection.name.to_owned(),
-            abilities: parse_abilities(unit_selection),
-            keywords,
-            invulnable_save: None,
+            entry_id: unit_selection.entry_id.to_owned(),
+            selection_id: unit_selection.id.to_owned(),
+            role: BattlefieldRole::from_categories(categories),
+            invulnerable_saves: InvulnerableSave::from_abilities(&abilities),
+            defensive_traits: DefensiveTraits::from_abilities(&abilities),
+            abilities,
+            keywords: Keywords::from_names(&keywords),
             models,
             rules,
-            points: Self::get_total_points(unit_selection),
+            points: get_total_cost(unit_selection, CostMode::Points.cost_name()),
+            power_level: get_total_cost(unit_selection, CostMode::PowerLevel.cost_name()),
+            psyker: parse_psyker(unit_selection)?,
+            psychic_powers: parse_psychic_powers(unit_selection)?,
+            transport: parse_transport(unit_selection),
+            warlord: parse_warlord(unit_selection),
+            warlord_trait: parse_warlord_trait(unit_selection),
+            relic: parse_relic(unit_selection),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::army::Army;
+    use crate::warhammer_data::{load_roster, load_unit};
+
+    fn wargear(army: &Army, unit_name: &str) -> Vec<String> {
+        let unit = army
+            .detachments
+            .iter()
+            .flat_map(|d| d.units.iter())
+            .find(|u| u.name == unit_name)
+            .unwrap();
+
+        let mut names: Vec<String> = unit
+            .models
+            .iter()
+            .flat_map(|m| m.wargear.iter())
+            .map(|w| w.name.to_owned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    #[test]
+    fn wargear_does_not_include_abilities() {
+        let army = Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap();
+
+        assert_eq!(
+            wargear(&army, "Leman Russ Battle Tanks"),
+            ["Dozer blade", "Track guards"]
+        );
+        assert_eq!(
+            wargear(&army, "Command Squad"),
+            ["Medi-pack", "Regimental Standard", "Vox-caster"]
+        );
+        // Warlord traits are not wargear
+        assert!(wargear(&army, "Lord Castellan Creed").is_empty());
+
+        // Abilities are still on the unit
+        let leman_russ = army.detachments[0]
+            .units
+            .iter()
+            .find(|u| u.name == "Leman Russ Battle Tanks")
+            .unwrap();
+        assert!(leman_russ
+            .abilities
+            .iter()
+            .any(|a| a.name == "Slow and Purposeful"));
+    }
+
+    #[test]
+    fn free_abilities_on_a_datasheet_are_not_wargear() {
+        let army = Army::from_roster(&load_roster("Test_Roster.ros")).unwrap();
+
+        // The Crystal Targeting Matrix is a free upgrade that every Nightwing has
+        assert!(wargear(&army, "Nightwing").is_empty());
+    }
+
+    /// Removes the cost of an upgrade of the unit's first model.
+    fn make_free(unit_selection: &mut Selection, name: &str) {
+        let model = &mut unit_selection.selections.as_mut().unwrap().selections[0];
+        let selections = &mut model.selections.as_mut().unwrap().selections;
+        let selection = selections.iter_mut().find(|s| s.name == name).unwrap();
+        for cost in &mut selection.costs.as_mut().unwrap().costs {
+            cost.value = 0.0;
+        }
+    }
+
+    #[test]
+    fn free_options_grouped_with_bought_wargear_are_wargear() {
+        let mut roster = load_roster("Astra_1000/Astra_1000.ros");
+        let leman_russ = roster.forces.forces[0]
+            .selections
+            .selections
+            .iter_mut()
+            .find(|s| s.name == "Leman Russ Battle Tanks")
+            .unwrap();
+        let wargear = |unit_selection: &Selection| -> Vec<String> {
+            let unit = Unit::from_selection(unit_selection).unwrap();
+            unit.models[0]
+                .wargear
+                .iter()
+                .map(|w| w.name.to_owned())
+                .collect()
+        };
+
+        // Track guards are in the same entry group as the Dozer blade, which is still bought
+        make_free(leman_russ, "Track guards");
+        assert_eq!(wargear(leman_russ), ["Track guards", "Dozer blade"]);
+
+        // Without anything bought from the group they are like "Slow and Purposeful"
+        make_free(leman_russ, "Dozer blade");
+        assert!(wargear(leman_russ).is_empty());
+    }
+
+    #[test]
+    fn invulnable_save_is_the_best_unconditional_save() {
+        let commissar = load_unit("Astra_1000/Astra_1000.ros", "Lord Commissar");
+        assert_eq!(commissar.invulnable_save(), Some(5));
+
+        let conscripts = load_unit("Astra_1000/Astra_1000.ros", "Conscripts");
+        assert_eq!(conscripts.invulnable_save(), None);
+    }
+
+    #[test]
+    fn damaged_profiles_follow_wound_bands() {
+        let leman_russ = load_unit("Astra_1000/Astra_1000.ros", "Leman Russ Battle Tanks");
+        let model = &leman_russ.models[0];
+        let skill = |wounds| model.profile_for_wounds(wounds).map(|p| p.ballistic_skill);
+
+        assert_eq!(model.wounds(), 12);
+        assert_eq!(skill(12), Some(4));
+        assert_eq!(skill(7), Some(4));
+        assert_eq!(skill(6), Some(5));
+        assert_eq!(skill(4), Some(5));
+        assert_eq!(skill(3), Some(6));
+        assert_eq!(skill(1), Some(6));
+        assert_eq!(skill(0), None);
+        // Wounds above the top band use the top band
+        assert_eq!(skill(20), Some(4));
+
+        let mut bands: Vec<WoundBand> =
+            model.profiles.iter().filter_map(|p| p.wound_band).collect();
+        bands.sort_by_key(|band| band.min);
+        assert_eq!(
+            bands,
+            [
+                WoundBand { min: 1, max: 3 },
+                WoundBand { min: 4, max: 6 },
+                WoundBand { min: 7, max: 12 },
+            ]
+        );
+    }
+
+    #[test]
+    fn split_gives_each_model_one_of_the_weapons() {
+        let squad = load_unit("Astra_1000/Astra_1000.ros", "Heavy Weapons Squad");
+        let mortar = squad.models[0]
+            .weapons
+            .iter()
+            .find(|w| w.name == "Mortar")
+            .unwrap();
+        let weapon = |name: &str| Weapon {
+            name: name.to_string(),
+            number: 2,
+            ..mortar.clone()
+        };
+
+        let model = Model {
+            number: 4,
+            weapons: vec![weapon("Heavy bolter"), weapon("Lascannon")],
+            wargear: Vec::new(),
+            ..squad.models[0].clone()
+        };
+
+        let loadouts: Vec<Vec<(String, u8)>> = model
+            .split()
+            .iter()
+            .map(|m| {
+                m.weapons
+                    .iter()
+                    .map(|w| (w.name.to_owned(), w.number))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            loadouts,
+            [
+                [("Heavy bolter".to_string(), 1)],
+                [("Heavy bolter".to_string(), 1)],
+                [("Lascannon".to_string(), 1)],
+                [("Lascannon".to_string(), 1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn individual_models_of_the_heavy_weapons_squad() {
+        let squad = load_unit("Astra_1000/Astra_1000.ros", "Heavy Weapons Squad");
+        let models = squad.individual_models();
+
+        assert_eq!(models.len(), 3);
+        for model in &models {
+            assert_eq!(model.number, 1);
+            let weapons: Vec<(&str, u8)> = model
+                .weapons
+                .iter()
+                .map(|w| (w.name.as_str(), w.number))
+                .collect();
+            assert_eq!(
+                weapons,
+                [("Lasgun", 1), ("Mortar", 1), ("Frag grenades", 1)]
+            );
+        }
+    }
+}
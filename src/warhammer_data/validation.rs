@@ -0,0 +1,461 @@
+use super::army::{Army, Detachment};
+use super::edition::Edition;
+use super::keyword::BattlefieldRole;
+
+/// 9th edition detachment types from the name of a force (i.e. "Battalion Detachment 0CP").
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetachmentType {
+    Patrol,
+    Battalion,
+    Brigade,
+    Vanguard,
+    Spearhead,
+    Outrider,
+    SuperHeavy,
+    SuperHeavyAuxiliary,
+    AirWing,
+    FortificationNetwork,
+}
+
+/// Number of units a detachment has for a battlefield role and how many it needs.
+#[derive(Debug, Clone, Copy)]
+pub struct SlotCount {
+    pub role: BattlefieldRole,
+    pub count: u8,
+    pub min: u8,
+    pub max: u8,
+}
+
+/// Result of checking the units of a detachment against its force organisation slots.
+#[derive(Debug, Clone)]
+pub struct DetachmentValidation {
+    pub detachment_type: Option<DetachmentType>,
+    pub slots: Vec<SlotCount>,
+    pub problems: Vec<String>,
+}
+
+/// Number of copies of a datasheet in an army and how many are allowed.
+#[derive(Debug, Clone)]
+pub struct DatasheetCount {
+    pub name: String,
+    pub entry_id: String,
+    pub count: u8,
+    /// Datasheets without a limit are None.
+    pub limit: Option<u8>,
+}
+
+/// Result of checking the number of copies of each datasheet in an army (the Rule of Three).
+#[derive(Debug, Clone)]
+pub struct DatasheetValidation {
+    pub datasheets: Vec<DatasheetCount>,
+    pub problems: Vec<String>,
+}
+
+/// Roles that are counted against the slots of a detachment, in the order they are reported.
+const ROLES: [BattlefieldRole; 8] = [
+    BattlefieldRole::HQ,
+    BattlefieldRole::Troops,
+    BattlefieldRole::Elites,
+    BattlefieldRole::FastAttack,
+    BattlefieldRole::HeavySupport,
+    BattlefieldRole::Flyer,
+    BattlefieldRole::LordOfWar,
+    BattlefieldRole::Fortification,
+];
+
+impl DetachmentType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        // Super-Heavy Auxiliary has to be checked before Super-Heavy
+        let types = [
+            ("Patrol", DetachmentType::Patrol),
+            ("Battalion", DetachmentType::Battalion),
+            ("Brigade", DetachmentType::Brigade),
+            ("Vanguard", DetachmentType::Vanguard),
+            ("Spearhead", DetachmentType::Spearhead),
+            ("Outrider", DetachmentType::Outrider),
+            ("Super-Heavy Auxiliary", DetachmentType::SuperHeavyAuxiliary),
+            ("Super-Heavy", DetachmentType::SuperHeavy),
+            ("Air Wing", DetachmentType::AirWing),
+            (
+                "Fortification Network",
+                DetachmentType::FortificationNetwork,
+            ),
+        ];
+
+        types
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map(|(_, detachment_type)| *detachment_type)
+    }
+
+    /// Command Points the detachment costs in 9th edition. Battalions are free, the other
+    /// detachments cost CP (i.e. "Super-Heavy Auxiliary Detachment -3CP").
+    pub fn command_cost(&self) -> u8 {
+        match self {
+            DetachmentType::Battalion => 0,
+            DetachmentType::Patrol => 2,
+            DetachmentType::FortificationNetwork => 1,
+            _ => 3,
+        }
+    }
+
+    /// Minimum and maximum number of units for a role. Roles that are not part of the detachment
+    /// have a maximum of 0.
+    pub fn slots(&self, role: BattlefieldRole) -> (u8, u8) {
+        use BattlefieldRole::*;
+
+        match (self, role) {
+            (DetachmentType::Patrol, HQ) => (1, 2),
+            (DetachmentType::Patrol, Troops) => (1, 3),
+            (DetachmentType::Patrol, Elites | FastAttack | HeavySupport | Flyer) => (0, 2),
+
+            (DetachmentType::Battalion, HQ) => (2, 3),
+            (DetachmentType::Battalion, Troops) => (3, 6),
+            (DetachmentType::Battalion, Elites) => (0, 6),
+            (DetachmentType::Battalion, FastAttack | HeavySupport) => (0, 3),
+            (DetachmentType::Battalion, Flyer) => (0, 2),
+
+            (DetachmentType::Brigade, HQ) => (3, 5),
+            (DetachmentType::Brigade, Troops) => (6, 12),
+            (DetachmentType::Brigade, Elites) => (3, 8),
+            (DetachmentType::Brigade, FastAttack | HeavySupport) => (3, 5),
+            (DetachmentType::Brigade, Flyer) => (0, 2),
+
+            (DetachmentType::Vanguard, HQ) => (1, 2),
+            (DetachmentType::Vanguard, Troops) => (0, 3),
+            (DetachmentType::Vanguard, Elites) => (3, 6),
+            (DetachmentType::Vanguard, FastAttack | HeavySupport | Flyer) => (0, 2),
+
+            (DetachmentType::Spearhead, HQ) => (1, 2),
+            (DetachmentType::Spearhead, Troops) => (0, 3),
+            (DetachmentType::Spearhead, HeavySupport) => (3, 6),
+            (DetachmentType::Spearhead, Elites | FastAttack | Flyer) => (0, 2),
+
+            (DetachmentType::Outrider, HQ) => (1, 2),
+            (DetachmentType::Outrider, Troops) => (0, 3),
+            (DetachmentType::Outrider, FastAttack) => (3, 6),
+            (DetachmentType::Outrider, Elites | HeavySupport | Flyer) => (0, 2),
+
+            (DetachmentType::SuperHeavy, LordOfWar) => (3, 5),
+            (DetachmentType::SuperHeavyAuxiliary, LordOfWar) => (1, 1),
+            (DetachmentType::AirWing, Flyer) => (3, 5),
+            (DetachmentType::FortificationNetwork, Fortification) => (1, 3),
+
+            // Every other detachment can take a fortification
+            (
+                DetachmentType::Patrol
+                | DetachmentType::Battalion
+                | DetachmentType::Brigade
+                | DetachmentType::Vanguard
+                | DetachmentType::Spearhead
+                | DetachmentType::Outrider,
+                Fortification,
+            ) => (0, 1),
+
+            _ => (0, 0),
+        }
+    }
+}
+
+impl DetachmentValidation {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Counts the units of each role and checks them against the detachment type's slots.
+    /// Dedicated Transports are limited to one for each other unit in the detachment.
+    pub fn from_detachment(detachment: &Detachment) -> Self {
+        let mut validation = Self {
+            detachment_type: DetachmentType::from_name(&detachment.name),
+            slots: Vec::new(),
+            problems: Vec::new(),
+        };
+
+        let detachment_type = match validation.detachment_type {
+            Some(detachment_type) => detachment_type,
+            None => {
+                validation
+                    .problems
+                    .push("Unknown detachment type: ".to_string() + &detachment.name);
+                return validation;
+            }
+        };
+
+        let count = |role: BattlefieldRole| {
+            detachment
+                .units
+                .iter()
+                .filter(|u| u.role == Some(role))
+                .count() as u8
+        };
+
+        for role in ROLES {
+            let (min, max) = detachment_type.slots(role);
+            let slot = SlotCount {
+                role,
+                count: count(role),
+                min,
+                max,
+            };
+
+            if slot.count < slot.min {
+                validation.problems.push(format!(
+                    "{} needs at least {} {}, has {}",
+                    detachment.name,
+                    slot.min,
+                    role.name(),
+                    slot.count
+                ));
+            } else if slot.count > slot.max {
+                validation.problems.push(format!(
+                    "{} can have at most {} {}, has {}",
+                    detachment.name,
+                    slot.max,
+                    role.name(),
+                    slot.count
+                ));
+            }
+
+            validation.slots.push(slot);
+        }
+
+        let transports = count(BattlefieldRole::DedicatedTransport);
+        let other_units = detachment
+            .units
+            .iter()
+            .filter(|u| {
+                !matches!(
+                    u.role,
+                    Some(BattlefieldRole::DedicatedTransport)
+                        | Some(BattlefieldRole::NoForceOrgSlot)
+                )
+            })
+            .count() as u8;
+        if transports > other_units {
+            validation.problems.push(format!(
+                "{} has {} Dedicated Transport for {} other units",
+                detachment.name, transports, other_units
+            ));
+        }
+
+        for unit in detachment.units.iter().filter(|u| u.role.is_none()) {
+            validation
+                .problems
+                .push(unit.name.to_owned() + " does not have a battlefield role");
+        }
+
+        validation
+    }
+}
+
+/// Gets the datasheet part of an entry id. Entry ids start with the links to the datasheet
+/// (i.e. "578e-e480-ae27-5806::2d05-89c5-1926-3127"), which can be different for the same datasheet.
+pub fn datasheet_id(entry_id: &str) -> &str {
+    entry_id.rsplit("::").next().unwrap_or(entry_id)
+}
+
+impl DatasheetValidation {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Counts the units of each datasheet across every detachment and checks them against the
+    /// edition's limits.
+    pub fn from_army(army: &Army, edition: Edition) -> Self {
+        let mut datasheets: Vec<DatasheetCount> = Vec::new();
+
+        for unit in army.detachments.iter().flat_map(|d| d.units.iter()) {
+            let entry_id = datasheet_id(&unit.entry_id);
+            match datasheets.iter_mut().find(|d| d.entry_id == entry_id) {
+                Some(datasheet) => datasheet.count += 1,
+                None => datasheets.push(DatasheetCount {
+                    name: unit.name.to_owned(),
+                    entry_id: entry_id.to_owned(),
+                    count: 1,
+                    limit: edition.datasheet_limit(unit.role, &unit.keywords),
+                }),
+            }
+        }
+
+        let problems = datasheets
+            .iter()
+            .filter_map(|d| match d.limit {
+                Some(limit) if d.count > limit => Some(format!(
+                    "{} has {} copies, only {} are allowed",
+                    d.name, d.count, limit
+                )),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            datasheets,
+            problems,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::{load_roster, load_unit};
+
+    fn sample_army() -> Army {
+        Army::from_roster(&load_roster("Astra_1000/Astra_1000.ros")).unwrap()
+    }
+
+    #[test]
+    fn detachment_types_and_slots() {
+        assert_eq!(
+            DetachmentType::from_name("Super-Heavy Auxiliary Detachment -3CP"),
+            Some(DetachmentType::SuperHeavyAuxiliary)
+        );
+        assert_eq!(
+            DetachmentType::from_name("Super-Heavy Detachment -3CP"),
+            Some(DetachmentType::SuperHeavy)
+        );
+        assert_eq!(
+            DetachmentType::from_name("Auxiliary Support Detachment"),
+            None
+        );
+
+        assert_eq!(
+            DetachmentType::Battalion.slots(BattlefieldRole::Troops),
+            (3, 6)
+        );
+        assert_eq!(
+            DetachmentType::Patrol.slots(BattlefieldRole::Fortification),
+            (0, 1)
+        );
+        assert_eq!(
+            DetachmentType::SuperHeavyAuxiliary.slots(BattlefieldRole::LordOfWar),
+            (1, 1)
+        );
+        assert_eq!(DetachmentType::AirWing.slots(BattlefieldRole::HQ), (0, 0));
+    }
+
+    #[test]
+    fn sample_detachments_are_valid() {
+        let army = sample_army();
+
+        for detachment in &army.detachments {
+            let validation = DetachmentValidation::from_detachment(detachment);
+            assert!(validation.is_valid(), "{:?}", validation.problems);
+        }
+
+        let battalion = DetachmentValidation::from_detachment(&army.detachments[0]);
+        assert_eq!(battalion.detachment_type, Some(DetachmentType::Battalion));
+        let troops = battalion
+            .slots
+            .iter()
+            .find(|s| s.role == BattlefieldRole::Troops)
+            .unwrap();
+        assert_eq!((troops.count, troops.min, troops.max), (3, 3, 6));
+    }
+
+    #[test]
+    fn too_few_and_too_many_units() {
+        let mut army = sample_army();
+
+        let battalion = &mut army.detachments[0];
+        battalion.units.retain(|u| u.name != "Conscripts");
+        assert_eq!(
+            DetachmentValidation::from_detachment(battalion).problems,
+            ["Battalion Detachment 0CP needs at least 3 Troops, has 0"]
+        );
+
+        let knights = &mut army.detachments[1];
+        knights
+            .units
+            .push(load_unit("Astra_1000/Astra_1000.ros", "Armiger Helverins"));
+        assert_eq!(
+            DetachmentValidation::from_detachment(knights).problems,
+            ["Super-Heavy Auxiliary Detachment -3CP can have at most 1 Lord of War, has 2"]
+        );
+    }
+
+    #[test]
+    fn dedicated_transports_need_other_units() {
+        let mut army = sample_army();
+        let battalion = &mut army.detachments[0];
+
+        // Dedicated Transports do not use a slot, but need another unit each
+        let transport = || {
+            let mut unit = load_unit("Astra_1000/Astra_1000.ros", "Heavy Weapons Squad");
+            unit.role = Some(BattlefieldRole::DedicatedTransport);
+            unit
+        };
+        battalion.units.push(transport());
+        assert!(DetachmentValidation::from_detachment(battalion).is_valid());
+
+        let knights = &mut army.detachments[1];
+        knights.units.push(transport());
+        knights.units.push(transport());
+        assert_eq!(
+            DetachmentValidation::from_detachment(knights).problems,
+            ["Super-Heavy Auxiliary Detachment -3CP has 2 Dedicated Transport for 1 other units"]
+        );
+    }
+
+    #[test]
+    fn datasheet_ids_ignore_the_links() {
+        assert_eq!(
+            datasheet_id("578e-e480-ae27-5806::2d05-89c5-1926-3127"),
+            "2d05-89c5-1926-3127"
+        );
+        assert_eq!(datasheet_id("2d05-89c5-1926-3127"), "2d05-89c5-1926-3127");
+    }
+
+    #[test]
+    fn rule_of_three() {
+        let mut army = sample_army();
+
+        let validation = DatasheetValidation::from_army(&army, Edition::Ninth);
+        assert!(validation.is_valid(), "{:?}", validation.problems);
+        let conscripts = validation
+            .datasheets
+            .iter()
+            .find(|d| d.name == "Conscripts")
+            .unwrap();
+        assert_eq!((conscripts.count, conscripts.limit), (3, None));
+
+        for _ in 0..3 {
+            army.detachments[0].units.push(load_unit(
+                "Astra_1000/Astra_1000.ros",
+                "Leman Russ Battle Tanks",
+            ));
+        }
+        assert_eq!(
+            DatasheetValidation::from_army(&army, Edition::Ninth).problems,
+            ["Leman Russ Battle Tanks has 4 copies, only 3 are allowed"]
+        );
+    }
+
+    #[test]
+    fn troops_and_dedicated_transports_are_not_limited() {
+        let mut army = sample_army();
+
+        army.detachments[0]
+            .units
+            .push(load_unit("Astra_1000/Astra_1000.ros", "Conscripts"));
+        for _ in 0..3 {
+            army.detachments[0].units.push(load_unit(
+                "Astra_1000/Astra_1000.ros",
+                "Leman Russ Battle Tanks",
+            ));
+        }
+        // Treat every copy of the tank as a transport
+        for unit in army.detachments[0].units.iter_mut() {
+            if unit.name == "Leman Russ Battle Tanks" {
+                unit.role = Some(BattlefieldRole::DedicatedTransport);
+            }
+        }
+        assert!(DatasheetValidation::from_army(&army, Edition::Ninth).is_valid());
+
+        // 10th edition only allows more copies of Battleline units
+        assert_eq!(
+            DatasheetValidation::from_army(&army, Edition::Tenth).problems,
+            ["Conscripts has 4 copies, only 3 are allowed"]
+        );
+    }
+}
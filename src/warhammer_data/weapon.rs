@@ -7,7 +7,7 @@ use crate::warhammer_data::ros_parser::ros_parser::{Characteristics, Selection};
 
 use super::ros_parser::ros_parser::Profile;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum WeaponType {
     Pistol,
     Assault,
@@ -17,20 +17,31 @@ pub enum WeaponType {
     Melee,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WeaponStrength {
     pub value: u8,
     pub strength_type: WeaponStrengthType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WeaponStrengthType {
     Addition,
     Multiply,
     Flat,
 }
 
-#[derive(Debug, Clone)]
+impl WeaponStrength {
+    /// Gets the strength of an attack made with the weapon by a model with the given strength.
+    pub fn effective(&self, user_strength: u8) -> u8 {
+        match self.strength_type {
+            WeaponStrengthType::Addition => user_strength.saturating_add(self.value),
+            WeaponStrengthType::Multiply => user_strength.saturating_mul(self.value),
+            WeaponStrengthType::Flat => self.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct WeaponProfile {
     pub abilities: Option<Vec<Ability>>,
     pub range: Option<u8>,
@@ -41,6 +52,22 @@ pub struct WeaponProfile {
     pub damage: ProfileValue,
 }
 
+/// How the firing model moved this turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovementState {
+    RemainedStationary,
+    NormalMove,
+    Advanced,
+    FellBack,
+}
+
+/// Shots and hit modifier for a weapon after applying its weapon type rules.
+#[derive(Debug, Clone, Copy)]
+pub struct ShotProfile {
+    pub shots: ProfileValue,
+    pub hit_modifier: i8,
+}
+
 #[derive(Debug, Clone)]
 pub struct Weapon {
     pub name: String,
@@ -147,6 +174,13 @@ impl WeaponProfile {
                                     }
                                 },
                             },
+                            // User is the same as the model's strength
+                            None if characteristic.value.as_ref().unwrap() == "User" => {
+                                WeaponStrength {
+                                    value: 0,
+                                    strength_type: WeaponStrengthType::Addition,
+                                }
+                            }
                             None => WeaponStrength {
                                 value: characteristic.value.as_ref().unwrap().parse().unwrap(),
                                 strength_type: WeaponStrengthType::Flat,
@@ -164,24 +198,24 @@ impl WeaponProfile {
                     weapon.damage = ProfileValue::from_str(characteristic.value.as_ref().unwrap())
                 }
                 // Parse ability.
-                "Abilities" => {
-                    if characteristic.value.as_ref().unwrap() != "-" {
-                        match weapon.abilities {
-                            // Push new ability if vector has already been created
-                            Some(ref mut f) => f.push(Ability {
-                                value: characteristic.value.as_ref().unwrap().to_string(),
+                // Relic weapons can have an empty abilities characteristic
+                "Abilities" => match characteristic.value.as_deref() {
+                    None | Some("-") => (),
+                    Some(value) => match weapon.abilities {
+                        // Push new ability if vector has already been created
+                        Some(ref mut f) => f.push(Ability {
+                            value: value.to_string(),
+                            name: "".to_string(),
+                        }),
+                        // Create vector and add ability to it since abilities is None and wants to stay None if there isn't any
+                        None => {
+                            weapon.abilities = Some(vec![Ability {
+                                value: value.to_string(),
                                 name: "".to_string(),
-                            }),
-                            // Create vector and add ability to it since abilities is None and wants to stay None if there isn't any
-                            None => {
-                                weapon.abilities = Some(vec![Ability {
-                                    value: characteristic.value.as_ref().unwrap().to_string(),
-                                    name: "".to_string(),
-                                }])
-                            }
+                            }])
                         }
-                    }
-                }
+                    },
+                },
                 _ => {
                     return Err("Unknown characteristicfor unit: ".to_owned()
                         + characteristic.name.as_str())
@@ -191,8 +225,28 @@ impl WeaponProfile {
 
         Ok(weapon)
     }
+
+    /// Checks if the weapon's abilities make its attacks hit without rolling (i.e. flamers).
+    pub fn automatically_hits(&self) -> bool {
+        match &self.abilities {
+            Some(abilities) => abilities
+                .iter()
+                .any(|ability| ability.value.contains("automatically hits")),
+            None => false,
+        }
+    }
 }
 
+/// How a weapon from an upgrade selection (i.e. a relic) changes a model's existing weapons.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadoutChange {
+    Add,
+    Replace(String),
+}
+
+/// Engagement range in inches.
+pub const ENGAGEMENT_RANGE: f32 = 1.0;
+
 impl Weapon {
     pub fn from_selection(weapon_selection: &Selection) -> Result<Self, String> {
         // Create and return weapon
@@ -215,4 +269,362 @@ impl Weapon {
             number: 1,
         })
     }
+
+    /// Gets the shots this weapon makes at a target the given distance away, applying the rules for the weapon type.
+    /// Returns None if the weapon cannot shoot at the target. Which pistols and grenades a model can fire
+    /// alongside its other weapons is handled by `choose_ranged_weapons`.
+    pub fn shots_at(&self, movement: MovementState, target_distance: f32) -> Option<ShotProfile> {
+        let range = self.profile.range? as f32;
+        let attacks = self.profile.attacks?;
+
+        if target_distance > range {
+            return None;
+        }
+
+        // Only pistols can be fired while within engagement range
+        let in_engagement_range = target_distance <= ENGAGEMENT_RANGE;
+
+        let mut hit_modifier: i8 = 0;
+        let shots = match self.profile.weapon_type {
+            WeaponType::Melee => return None,
+            WeaponType::Pistol => attacks,
+            _ if in_engagement_range => return None,
+            // Rapid fire weapons double their shots at half range
+            WeaponType::RapidFire => {
+                if target_distance <= range / 2.0 {
+                    attacks.multiply(2)
+                } else {
+                    attacks
+                }
+            }
+            // Heavy weapons are -1 to hit if the model moved
+            WeaponType::Heavy => {
+                if movement != MovementState::RemainedStationary {
+                    hit_modifier -= 1;
+                }
+                attacks
+            }
+            WeaponType::Assault | WeaponType::Grenade => attacks,
+        };
+
+        match movement {
+            MovementState::FellBack => return None,
+            // Only assault weapons can be fired after advancing and they are -1 to hit
+            MovementState::Advanced => match self.profile.weapon_type {
+                WeaponType::Assault => hit_modifier -= 1,
+                _ => return None,
+            },
+            _ => (),
+        }
+
+        Some(ShotProfile {
+            shots: shots.multiply(self.number),
+            hit_modifier,
+        })
+    }
+}
+
+/// Gets the weapons a model fires at a target and their shots. A model fires either its pistols or
+/// its other ranged weapons, whichever makes more shots. Grenades are thrown instead of every other
+/// weapon, and only one model in a unit can throw one, so they are only used if `can_throw_grenade`.
+pub fn choose_ranged_weapons(
+    weapons: &[Weapon],
+    movement: MovementState,
+    target_distance: f32,
+    can_throw_grenade: bool,
+) -> Vec<(&Weapon, ShotProfile)> {
+    let fired = |weapon_types: &[WeaponType]| -> Vec<(&Weapon, ShotProfile)> {
+        weapons
+            .iter()
+            .filter(|w| weapon_types.contains(&w.profile.weapon_type))
+            .filter_map(|w| {
+                w.shots_at(movement, target_distance)
+                    .map(|shots| (w, shots))
+            })
+            .collect()
+    };
+    let total_shots = |fired: &[(&Weapon, ShotProfile)]| -> f32 {
+        fired
+            .iter()
+            .map(|(_, shot_profile)| shot_profile.shots.average())
+            .sum()
+    };
+
+    let mut options = vec![
+        fired(&[
+            WeaponType::Assault,
+            WeaponType::Heavy,
+            WeaponType::RapidFire,
+        ]),
+        fired(&[WeaponType::Pistol]),
+    ];
+    // A grenade is thrown on its own, with a single grenade weapon
+    if can_throw_grenade {
+        for grenade in fired(&[WeaponType::Grenade]) {
+            options.push(vec![(
+                grenade.0,
+                ShotProfile {
+                    shots: grenade.0.profile.attacks.unwrap(),
+                    hit_modifier: grenade.1.hit_modifier,
+                },
+            )]);
+        }
+    }
+
+    // Earlier options are kept on a tie
+    let mut best: Vec<(&Weapon, ShotProfile)> = Vec::new();
+    for option in options {
+        if total_shots(&option) > total_shots(&best) {
+            best = option;
+        }
+    }
+
+    best
+}
+
+impl LoadoutChange {
+    /// Resolves how an upgrade selection's weapon is added to a model.
+    /// The upgrade's rules text is checked first for the weapon it replaces
+    /// ("replaces the model's power sword"), otherwise the upgrade replaces a weapon
+    /// that was chosen from the same entry group.
+    pub fn from_selection(upgrade_selection: &Selection, weapon_selections: &[&Selection]) -> Self {
+        lazy_static! {
+            // Regex to get the replaced weapon from the upgrade description
+            static ref REPLACE_RE: Regex = Regex::new(
+                r"(?i)replaces (?:the|this|a|one of the) (?:(?:model|bearer)(?:'|’)s )?([\w\- ]+?)(?: and|,|\.|$)"
+            )
+            .unwrap();
+        }
+
+        for profile in &upgrade_selection.profiles.as_ref().unwrap().profiles {
+            if profile.type_name != "Abilities" {
+                continue;
+            }
+
+            for characteristic in &profile.characteristics.characteristics {
+                let description = characteristic.value.as_deref().unwrap_or("");
+                if let Some(captures) = REPLACE_RE.captures(description) {
+                    return LoadoutChange::Replace(captures[1].to_string());
+                }
+            }
+        }
+
+        // Options in the same entry group are alternatives to each other
+        if let Some(group) = &upgrade_selection.entry_group_id {
+            for weapon_selection in weapon_selections {
+                if weapon_selection.entry_group_id.as_ref() == Some(group) {
+                    return LoadoutChange::Replace(weapon_selection.name.to_owned());
+                }
+            }
+        }
+
+        LoadoutChange::Add
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warhammer_data::{load_roster, load_unit};
+
+    fn weapon(weapon_type: WeaponType, range: u8, attacks: &str) -> Weapon {
+        Weapon {
+            name: format!("{:?}", weapon_type),
+            profile: WeaponProfile {
+                abilities: None,
+                range: Some(range),
+                weapon_type,
+                attacks: Some(ProfileValue::from_str(attacks)),
+                strength: WeaponStrength {
+                    value: 3,
+                    strength_type: WeaponStrengthType::Flat,
+                },
+                armour_penetration: 0,
+                damage: ProfileValue::from_str("1"),
+            },
+            number: 1,
+        }
+    }
+
+    fn shots(weapon: &Weapon, movement: MovementState, distance: f32) -> Option<(f32, i8)> {
+        weapon
+            .shots_at(movement, distance)
+            .map(|s| (s.shots.average(), s.hit_modifier))
+    }
+
+    #[test]
+    fn rapid_fire_doubles_at_half_range() {
+        let lasgun = weapon(WeaponType::RapidFire, 24, "1");
+
+        assert_eq!(
+            shots(&lasgun, MovementState::NormalMove, 12.0),
+            Some((2.0, 0))
+        );
+        assert_eq!(
+            shots(&lasgun, MovementState::NormalMove, 12.5),
+            Some((1.0, 0))
+        );
+        assert_eq!(shots(&lasgun, MovementState::NormalMove, 24.5), None);
+    }
+
+    #[test]
+    fn heavy_is_minus_one_to_hit_after_moving() {
+        let lascannon = weapon(WeaponType::Heavy, 48, "1");
+
+        assert_eq!(
+            shots(&lascannon, MovementState::RemainedStationary, 24.0),
+            Some((1.0, 0))
+        );
+        assert_eq!(
+            shots(&lascannon, MovementState::NormalMove, 24.0),
+            Some((1.0, -1))
+        );
+    }
+
+    #[test]
+    fn only_assault_weapons_fire_after_advancing() {
+        let advanced = MovementState::Advanced;
+
+        assert_eq!(
+            shots(&weapon(WeaponType::Assault, 24, "2"), advanced, 12.0),
+            Some((2.0, -1))
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::Heavy, 48, "1"), advanced, 12.0),
+            None
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::RapidFire, 24, "1"), advanced, 12.0),
+            None
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::Pistol, 12, "1"), advanced, 6.0),
+            None
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::Grenade, 6, "D6"), advanced, 6.0),
+            None
+        );
+    }
+
+    #[test]
+    fn no_weapons_fire_after_falling_back() {
+        for weapon_type in [
+            WeaponType::Assault,
+            WeaponType::Pistol,
+            WeaponType::RapidFire,
+        ] {
+            assert_eq!(
+                shots(&weapon(weapon_type, 24, "1"), MovementState::FellBack, 6.0),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn only_pistols_fire_in_engagement_range() {
+        let stationary = MovementState::RemainedStationary;
+
+        assert_eq!(
+            shots(&weapon(WeaponType::Pistol, 12, "1"), stationary, 1.0),
+            Some((1.0, 0))
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::RapidFire, 24, "1"), stationary, 1.0),
+            None
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::Grenade, 6, "D6"), stationary, 1.0),
+            None
+        );
+        assert_eq!(
+            shots(&weapon(WeaponType::Melee, 1, "1"), stationary, 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn shots_are_multiplied_by_number() {
+        let mut lasgun = weapon(WeaponType::RapidFire, 24, "1");
+        lasgun.number = 10;
+
+        assert_eq!(
+            shots(&lasgun, MovementState::NormalMove, 6.0),
+            Some((20.0, 0))
+        );
+    }
+
+    fn chosen(weapons: &[Weapon], distance: f32, can_throw_grenade: bool) -> Vec<String> {
+        choose_ranged_weapons(
+            weapons,
+            MovementState::NormalMove,
+            distance,
+            can_throw_grenade,
+        )
+        .iter()
+        .map(|(weapon, _)| weapon.name.to_owned())
+        .collect()
+    }
+
+    #[test]
+    fn pistols_are_not_fired_with_other_weapons() {
+        let weapons = [
+            weapon(WeaponType::Pistol, 12, "1"),
+            weapon(WeaponType::RapidFire, 24, "1"),
+            weapon(WeaponType::Heavy, 36, "2"),
+        ];
+
+        assert_eq!(chosen(&weapons, 6.0, false), ["RapidFire", "Heavy"]);
+        // Pistols make more shots when the other weapons can not fire
+        assert_eq!(chosen(&weapons, 1.0, false), ["Pistol"]);
+    }
+
+    #[test]
+    fn grenades_are_thrown_instead_of_other_weapons() {
+        let mut grenades = weapon(WeaponType::Grenade, 6, "D6");
+        grenades.number = 2;
+        let weapons = [weapon(WeaponType::RapidFire, 24, "1"), grenades];
+
+        assert_eq!(chosen(&weapons, 6.0, false), ["RapidFire"]);
+        assert_eq!(chosen(&weapons, 6.0, true), ["Grenade"]);
+        // Only a single grenade is thrown
+        let fired = choose_ranged_weapons(&weapons, MovementState::NormalMove, 6.0, true);
+        assert_eq!(fired[0].1.shots.average(), 3.5);
+        // Grenades that are out of range are not thrown
+        assert_eq!(chosen(&weapons, 12.0, true), ["RapidFire"]);
+    }
+
+    #[test]
+    fn relic_replaces_the_weapon_named_in_its_rules() {
+        let roster = load_roster("Astra_1000/Astra_1000.ros");
+        let commissar = roster.forces.forces[0]
+            .selections
+            .selections
+            .iter()
+            .find(|s| s.name == "Lord Commissar")
+            .unwrap();
+        let selections: Vec<&Selection> = commissar
+            .selections
+            .iter()
+            .flat_map(|s| s.selections.iter())
+            .collect();
+        let relic = selections
+            .iter()
+            .find(|s| s.name == "Relic: The Blade of Conquest")
+            .unwrap();
+
+        assert_eq!(
+            LoadoutChange::from_selection(relic, &selections),
+            LoadoutChange::Replace("power sword".to_string())
+        );
+
+        let commissar = load_unit("Astra_1000/Astra_1000.ros", "Lord Commissar");
+        let mut names: Vec<&str> = commissar.models[0]
+            .weapons
+            .iter()
+            .map(|w| w.name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["Bolt pistol", "The Blade of Conquest"]);
+    }
 }